@@ -0,0 +1,116 @@
+use std::{
+    error::Error,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::content_negotiation::accept::AcceptTokens;
+
+/**
+ * Content codings of precompressed and compressed responses, in the order of preference.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+/**
+ * Compression levels: the best one for precompression, and a fast one for compression on the fly.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Best,
+    Fast,
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 2] = [Encoding::Brotli, Encoding::Gzip];
+
+    /**
+     * A token of `Content-Encoding`.
+     */
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    /**
+     * A path of the precompressed sibling of the file.
+     */
+    pub fn sibling_path(&self, path: &Path) -> PathBuf {
+        let extension = match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gz",
+        };
+        let mut sibling = path.as_os_str().to_os_string();
+        sibling.push(".");
+        sibling.push(extension);
+        PathBuf::from(sibling)
+    }
+
+    pub fn compress(&self, content: &[u8], level: Level) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self {
+            Encoding::Brotli => {
+                let (quality, lgwin) = match level {
+                    Level::Best => (11, 22),
+                    Level::Fast => (4, 18),
+                };
+                let mut compressed = vec![];
+                {
+                    let mut writer =
+                        brotli::CompressorWriter::new(&mut compressed, 4096, quality, lgwin);
+                    writer.write_all(content)?;
+                }
+                Ok(compressed)
+            }
+            Encoding::Gzip => {
+                let compression = match level {
+                    Level::Best => flate2::Compression::best(),
+                    Level::Fast => flate2::Compression::fast(),
+                };
+                let mut encoder = flate2::write::GzEncoder::new(vec![], compression);
+                encoder.write_all(content)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+}
+
+/**
+ * Select an encoding acceptable by `Accept-Encoding` from available ones.
+ */
+pub fn negotiate(accept_encoding: &str, available: &[Encoding]) -> Option<Encoding> {
+    let mut accept = AcceptTokens::parse(accept_encoding);
+    // `x-gzip` is equivalent to `gzip` (RFC 9110, Section 8.4.1.3).
+    for range in &mut accept.ranges {
+        if range.token == "x-gzip" {
+            range.token = Encoding::Gzip.as_str().to_string();
+        }
+    }
+
+    let tokens: Vec<&str> = Encoding::ALL
+        .iter()
+        .filter(|encoding| available.contains(encoding))
+        .map(|encoding| encoding.as_str())
+        .collect();
+    let token = accept.negotiate(&tokens)?;
+    Encoding::ALL
+        .into_iter()
+        .find(|encoding| encoding.as_str() == token)
+}
+
+/**
+ * Whether the file is worth compressing, judged by its type. Media are already compressed.
+ */
+pub fn is_compressible(path: &Path) -> bool {
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    mime.type_() == "text"
+        || mime.subtype() == "json"
+        || mime.suffix().map(|x| x.as_str()) == Some("json")
+        || mime.subtype() == "xml"
+        || mime.suffix().map(|x| x.as_str()) == Some("xml")
+        || mime.subtype() == "javascript"
+}
@@ -0,0 +1,339 @@
+const ACTIVITY_JSON: &str = "application/activity+json";
+const LD_JSON: &str = "application/ld+json";
+const ACTIVITY_STREAMS_PROFILE: &str = "https://www.w3.org/ns/activitystreams";
+
+/**
+ * A media type like `text/html; charset=utf-8`, or a media range with wildcards in Accept headers.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaType {
+    pub typ: String,
+    pub subtype: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl MediaType {
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = split_unquoted(value, ';').into_iter();
+        let (typ, subtype) = parts.next()?.trim().split_once('/')?;
+        let typ = typ.trim().to_ascii_lowercase();
+        let subtype = subtype.trim().to_ascii_lowercase();
+        if typ.is_empty() || subtype.is_empty() || (typ == "*" && subtype != "*") {
+            return None;
+        }
+
+        let mut params = vec![];
+        for param in parts {
+            let param = param.trim();
+            if param.is_empty() {
+                continue;
+            }
+            let (name, value) = param.split_once('=')?;
+            let value = value.trim();
+            let value = match value.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+                Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+                None => value.to_string(),
+            };
+            params.push((name.trim().to_ascii_lowercase(), value));
+        }
+
+        Some(Self {
+            typ,
+            subtype,
+            params,
+        })
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param_name, _)| param_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.typ, self.subtype)
+    }
+
+    /**
+     * Whether the type is ActivityStreams JSON, i.e. `application/activity+json` or
+     * `application/ld+json` with the ActivityStreams profile or no profile.
+     */
+    pub fn is_activity_json(&self) -> bool {
+        match self.essence().as_str() {
+            ACTIVITY_JSON => true,
+            LD_JSON => match self.param("profile") {
+                None => true,
+                Some(profile) => profile
+                    .split_ascii_whitespace()
+                    .any(|x| x == ACTIVITY_STREAMS_PROFILE),
+            },
+            _ => false,
+        }
+    }
+}
+
+/**
+ * A media range with its quality in Accept headers.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaRange {
+    pub media_type: MediaType,
+    pub quality: f32,
+}
+
+impl MediaRange {
+    /**
+     * Precedence of ranges matching the same type: more specific ranges override less specific ones.
+     */
+    fn specificity(&self) -> u8 {
+        if self.media_type.typ == "*" {
+            0
+        } else if self.media_type.subtype == "*" {
+            1
+        } else if self.media_type.params.is_empty() {
+            2
+        } else {
+            3
+        }
+    }
+
+    fn matches(&self, media_type: &MediaType) -> bool {
+        let range = &self.media_type;
+        if range.typ == "*" {
+            return true;
+        }
+        if range.subtype == "*" {
+            return range.typ == media_type.typ;
+        }
+        if range.is_activity_json() && media_type.is_activity_json() {
+            return true;
+        }
+        range.essence() == media_type.essence()
+            && range
+                .params
+                .iter()
+                .all(|(name, value)| media_type.param(name) == Some(value))
+    }
+}
+
+/**
+ * A parsed Accept header.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Accept {
+    pub ranges: Vec<MediaRange>,
+}
+
+impl Accept {
+    /**
+     * Parse an Accept header, ignoring malformed media ranges.
+     */
+    pub fn parse(value: &str) -> Self {
+        let mut ranges = vec![];
+        for item in split_unquoted(value, ',') {
+            if item.trim().is_empty() {
+                continue;
+            }
+            let Some(mut media_type) = MediaType::parse(item) else {
+                continue;
+            };
+
+            let mut quality = 1.0;
+            if let Some(index) = media_type.params.iter().position(|(name, _)| name == "q") {
+                let (_, value) = media_type.params.remove(index);
+                // Accept extension parameters after q are not media type parameters.
+                media_type.params.truncate(index);
+                quality = match parse_quality(&value) {
+                    Some(x) => x,
+                    None => continue,
+                };
+            }
+
+            ranges.push(MediaRange {
+                media_type,
+                quality,
+            });
+        }
+        Self { ranges }
+    }
+
+    /**
+     * The quality of the media type, which is given by the most specific matching range.
+     */
+    pub fn quality(&self, media_type: &MediaType) -> f32 {
+        self.matching_range(media_type)
+            .map_or(0.0, |range| range.quality)
+    }
+
+    /**
+     * Choose the most acceptable one from available media types.
+     *
+     * Ties are broken by specificity of matching ranges, and then by the order of available media types.
+     */
+    pub fn negotiate<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        self.negotiate_with_min_specificity(available, 0)
+    }
+
+    /**
+     * Like `negotiate`, but ignores ranges of any types to leave such requests to a default
+     * representation.
+     */
+    pub fn negotiate_explicitly<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        self.negotiate_with_min_specificity(available, 1)
+    }
+
+    fn matching_range(&self, media_type: &MediaType) -> Option<&MediaRange> {
+        self.ranges
+            .iter()
+            .filter(|range| range.matches(media_type))
+            .max_by_key(|range| range.specificity())
+    }
+
+    fn negotiate_with_min_specificity<'a>(
+        &self,
+        available: &[&'a str],
+        min_specificity: u8,
+    ) -> Option<&'a str> {
+        let mut best: Option<(&'a str, f32, u8)> = None;
+        for candidate in available {
+            let Some(media_type) = MediaType::parse(candidate) else {
+                continue;
+            };
+            let Some(range) = self.matching_range(&media_type) else {
+                continue;
+            };
+            let specificity = range.specificity();
+            if range.quality <= 0.0 || specificity < min_specificity {
+                continue;
+            }
+            match best {
+                Some((_, best_quality, best_specificity))
+                    if best_quality > range.quality
+                        || (best_quality == range.quality && best_specificity >= specificity) =>
+                {
+                    // keep the current one
+                }
+                _ => best = Some((candidate, range.quality, specificity)),
+            }
+        }
+        best.map(|(candidate, _, _)| candidate)
+    }
+}
+
+/**
+ * A parsed header of tokens with qualities such as `Accept-Encoding`, e.g. `br, gzip;q=0.5, *;q=0`.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcceptTokens {
+    pub ranges: Vec<TokenRange>,
+}
+
+/**
+ * A lowercased token or `*` with its quality.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenRange {
+    pub token: String,
+    pub quality: f32,
+}
+
+impl AcceptTokens {
+    /**
+     * Parse a header, ignoring malformed items as `Accept::parse` does.
+     */
+    pub fn parse(value: &str) -> Self {
+        let mut ranges = vec![];
+        'items: for item in split_unquoted(value, ',') {
+            let mut parts = split_unquoted(item, ';').into_iter();
+            let token = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+            if token.is_empty() {
+                continue;
+            }
+
+            let mut quality = 1.0;
+            for param in parts {
+                let param = param.trim();
+                if param.is_empty() {
+                    continue;
+                }
+                let Some((name, value)) = param.split_once('=') else {
+                    continue 'items;
+                };
+                if name.trim().eq_ignore_ascii_case("q") {
+                    quality = match parse_quality(value.trim()) {
+                        Some(x) => x,
+                        None => continue 'items,
+                    };
+                }
+            }
+
+            ranges.push(TokenRange { token, quality });
+        }
+        Self { ranges }
+    }
+
+    /**
+     * The quality of the token, which is given by the range of the token, or by `*` if it is not listed.
+     */
+    pub fn quality(&self, token: &str) -> f32 {
+        let token = token.to_ascii_lowercase();
+        self.ranges
+            .iter()
+            .find(|range| range.token == token)
+            .or_else(|| self.ranges.iter().find(|range| range.token == "*"))
+            .map_or(0.0, |range| range.quality)
+    }
+
+    /**
+     * Choose the most acceptable one from available tokens. Ties are broken by the order of available tokens.
+     */
+    pub fn negotiate<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        let mut best: Option<(&'a str, f32)> = None;
+        for candidate in available {
+            let quality = self.quality(candidate);
+            if quality <= 0.0 {
+                continue;
+            }
+            match best {
+                Some((_, best_quality)) if best_quality >= quality => {}
+                _ => best = Some((candidate, quality)),
+            }
+        }
+        best.map(|(candidate, _)| candidate)
+    }
+}
+
+/**
+ * Split a header value by the separator outside of quoted strings, e.g. `a; profile="b, c"`.
+ */
+fn split_unquoted(value: &str, separator: char) -> Vec<&str> {
+    let mut items = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if quoted && c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if !quoted && c == separator {
+            items.push(&value[start..index]);
+            start = index + c.len_utf8();
+        }
+    }
+    items.push(&value[start..]);
+    items
+}
+
+/**
+ * A quality value from 0 to 1, shared by all Accept headers.
+ */
+fn parse_quality(value: &str) -> Option<f32> {
+    match value.parse::<f32>() {
+        Ok(x) if (0.0..=1.0).contains(&x) => Some(x),
+        _ => None,
+    }
+}
@@ -0,0 +1,46 @@
+use std::time::SystemTime;
+
+use httpdate::HttpDate;
+use sha2::{Digest, Sha256};
+
+/**
+ * A strong entity tag by the hash of the content.
+ */
+pub fn from_content(content: &[u8]) -> String {
+    from_digest(Sha256::new_with_prefix(content))
+}
+
+/**
+ * A strong entity tag by the digest of the content, which can be updated from a stream.
+ * It is the same as `from_content` for the same content.
+ */
+pub fn from_digest(digest: Sha256) -> String {
+    let hash = format!("{:x}", digest.finalize());
+    format!("\"{}\"", &hash[..32])
+}
+
+/**
+ * Whether an `If-None-Match` header matches the entity tag, by the weak comparison.
+ */
+pub fn if_none_match(header: &str, etag: &str) -> bool {
+    let opaque_tag = strip_weak(etag.trim());
+    header.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || (!candidate.is_empty() && strip_weak(candidate) == opaque_tag)
+    })
+}
+
+fn strip_weak(etag: &str) -> &str {
+    etag.strip_prefix("W/").unwrap_or(etag)
+}
+
+/**
+ * Whether the resource is not modified since the date of an `If-Modified-Since` header.
+ * Invalid dates never match.
+ */
+pub fn not_modified_since(header: &str, modified: SystemTime) -> bool {
+    match httpdate::parse_http_date(header.trim()) {
+        // HTTP dates are in seconds.
+        Ok(since) => SystemTime::from(HttpDate::from(modified)) <= since,
+        Err(_) => false,
+    }
+}
@@ -2,51 +2,57 @@ use activitist::json::JsonSerde;
 use activitist::model as ap_model;
 use reqwest::StatusCode;
 use std::error::Error;
+use url::Url;
+
+use super::http::{HttpClient, HttpError};
+
+pub struct Fetched<T> {
+    pub data: T,
+    pub redirected_from: Vec<Url>,
+}
 
 pub async fn fetch_actor(
-    client: &reqwest::Client,
+    client: &HttpClient,
     uri: String,
-) -> Result<ap_model::Object, Box<dyn Error>> {
-    let object: ap_model::Object = fetch_ap_resource(client, uri).await?;
-    if object.actor_items.is_none() {
-        Err(format!("Actor items are should be available.").into())
+) -> Result<Fetched<ap_model::Object>, Box<dyn Error>> {
+    let fetched: Fetched<ap_model::Object> = fetch_ap_resource(client, uri).await?;
+    if fetched.data.actor_items.is_none() {
+        Err("Actor items are should be available.".into())
     } else {
-        Ok(object)
+        Ok(fetched)
     }
 }
 
 pub async fn fetch_object(
-    client: &reqwest::Client,
+    client: &HttpClient,
     uri: String,
-) -> Result<ap_model::Object, Box<dyn Error>> {
-    let object: ap_model::Object = fetch_ap_resource(client, uri).await?;
-    Ok(object)
+) -> Result<Fetched<ap_model::Object>, Box<dyn Error>> {
+    let fetched: Fetched<ap_model::Object> = fetch_ap_resource(client, uri).await?;
+    Ok(fetched)
 }
 
 pub async fn fetch_ap_resource<T: JsonSerde>(
-    client: &reqwest::Client,
+    client: &HttpClient,
     uri: String,
-) -> Result<T, Box<dyn Error>> {
-    let current_uri: String = uri;
-    loop {
-        let response = client
-            .get(current_uri)
-            .header(reqwest::header::ACCEPT, "application/activity+json")
-            .send()
-            .await?;
-        match response.status() {
-            StatusCode::OK => {
-                // continue
-            }
-            x @ (StatusCode::FOUND | StatusCode::TEMPORARY_REDIRECT) => {
-                // TODO: support redirection
-                return Err(format!("Not supported redirection: status={x}").into());
+) -> Result<Fetched<T>, Box<dyn Error>> {
+    let response = client.get(&uri, Some("application/activity+json")).await?;
+    match response.status {
+        StatusCode::OK => {
+            // continue
+        }
+        x => {
+            return Err(HttpError::Permanent {
+                url: response.url,
+                reason: format!("Unknown response: status={x}"),
             }
-            x => return Err(format!("Unknown response: status={x}").into()),
+            .into())
         }
+    }
 
-        let data: T = T::from_json_bytes(&response.bytes().await?)?;
+    let data: T = T::from_json_bytes(&response.body)?;
 
-        return Ok(data);
-    }
+    Ok(Fetched {
+        data,
+        redirected_from: response.redirected_from,
+    })
 }
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+/**
+ * A state of crawling the outbox of an account, which is persisted to resume and update archives.
+ *
+ * Outbox pages are not persisted, but rebuilt from the archived activities in order.
+ */
+#[derive(Serialize, Deserialize, Default)]
+pub struct CrawlState {
+    /// A URL of the next outbox page to fetch, if the outbox is not fetched to the end.
+    pub resume_page_url: Option<String>,
+    /// Archived items in the order of the outbox, i.e. newer items first.
+    pub archived_items: Vec<ArchivedItem>,
+    /// Activities failed to be archived with `--continue-on-error`, which are retried on the next run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_items: Vec<FailedItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FailedItem {
+    /// An original ID of the activity.
+    pub activity_id: String,
+    /// An index of `archived_items` where the activity is placed in the outbox.
+    pub position: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ArchivedItem {
+    /// An original ID of the activity.
+    pub activity_id: Option<String>,
+    /// A hash of the activity embedded in the outbox without its ID, to find it instead of the ID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// A base path of the archived object, which contains `activity.json`.
+    pub base_path: String,
+}
+
+impl ArchivedItem {
+    /**
+     * A key to find the activity in outboxes: the ID, or the content hash if it has no ID.
+     */
+    pub fn key(&self) -> Option<&str> {
+        self.activity_id.as_deref().or(self.content_hash.as_deref())
+    }
+}
@@ -1,14 +1,26 @@
+use std::sync::Arc;
+
 use url::Url;
 
-use super::{output::Output, templates::Templates};
+use super::{
+    http::HttpClient, media::MirroredMedia, object_path::ObjectPathResolver, output::Output,
+    report::Reporter, templates::Templates,
+};
 
 pub struct Env<'a> {
-    pub client: reqwest::Client,
+    pub client: HttpClient,
     pub output: Output,
     pub templates: Templates<'a>,
 
     pub default_max_pages: usize,
     pub static_base_url: Url,
     pub fetch_outbox: bool,
+    pub fetch_media: bool,
+    pub mirrored_media: MirroredMedia,
     pub page_items_count: usize,
+    pub object_path_resolver: ObjectPathResolver,
+    pub concurrency: usize,
+    pub use_crawl_state: bool,
+    pub continue_on_error: bool,
+    pub reporter: Arc<Reporter>,
 }
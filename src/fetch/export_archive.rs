@@ -0,0 +1,68 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::Read,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use zip::ZipArchive;
+
+/**
+ * An exported archive, given as a zip file or an extracted directory.
+ *
+ * e.g. "Request your archive" of Mastodon contains `actor.json`, `outbox.json` and media files
+ * referred from them with relative paths.
+ */
+pub enum ExportArchive {
+    Zip(Arc<Mutex<ZipArchive<File>>>),
+    Dir(PathBuf),
+}
+
+impl ExportArchive {
+    pub async fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if tokio::fs::metadata(path).await?.is_dir() {
+            Ok(Self::Dir(path.to_path_buf()))
+        } else {
+            // Zip files are read by blocking I/O, which is moved off the async runtime.
+            let path = path.to_path_buf();
+            let archive = tokio::task::spawn_blocking(move || {
+                let file = File::open(&path).map_err(|err| err.to_string())?;
+                ZipArchive::new(file).map_err(|err| err.to_string())
+            })
+            .await??;
+            Ok(Self::Zip(Arc::new(Mutex::new(archive))))
+        }
+    }
+
+    pub async fn read(&self, name: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let name = name.trim_start_matches('/');
+        if !Path::new(name)
+            .components()
+            .all(|x| matches!(x, Component::Normal(_)))
+        {
+            return Err(format!("Illegal file name in the export: {name}").into());
+        }
+
+        match self {
+            Self::Dir(root) => Ok(tokio::fs::read(root.join(name)).await?),
+            Self::Zip(archive) => {
+                let archive = archive.clone();
+                let name = name.to_string();
+                let bytes = tokio::task::spawn_blocking(move || {
+                    let mut archive = match archive.lock() {
+                        Ok(x) => x,
+                        Err(err) => return Err(format!("Failed to lock the export: {err}")),
+                    };
+                    let mut file = archive.by_name(&name).map_err(|err| err.to_string())?;
+                    let mut bytes = Vec::with_capacity(file.size() as usize);
+                    file.read_to_end(&mut bytes)
+                        .map_err(|err| err.to_string())?;
+                    Ok(bytes)
+                })
+                .await??;
+                Ok(bytes)
+            }
+        }
+    }
+}
@@ -0,0 +1,420 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use clap::ValueEnum;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use url::Url;
+
+use super::signature::Signer;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum RedirectPolicy {
+    /// Never follow redirections.
+    None,
+    /// Follow redirections only to the same scheme, host and port.
+    SameOrigin,
+    /// Follow any redirections.
+    Any,
+}
+
+pub struct HttpClientOptions {
+    pub redirect_policy: RedirectPolicy,
+    pub max_redirects: usize,
+    pub max_requests_per_host: usize,
+    pub min_request_interval: Duration,
+    pub max_retries: usize,
+    pub initial_retry_backoff: Duration,
+    pub max_retry_backoff: Duration,
+    pub request_timeout: Duration,
+}
+
+/**
+ * A failure of HTTP requests.
+ *
+ * Transient failures (rate limits, server errors, timeouts and broken connections) are retried
+ * until retries are exhausted, and permanent failures are never retried.
+ */
+#[derive(Debug)]
+pub enum HttpError {
+    Transient { url: Url, reason: String },
+    Permanent { url: Url, reason: String },
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::Transient { url, reason } => {
+                write!(f, "Transient HTTP failure: {reason}, url={url}")
+            }
+            HttpError::Permanent { url, reason } => {
+                write!(f, "Permanent HTTP failure: {reason}, url={url}")
+            }
+        }
+    }
+}
+
+impl Error for HttpError {}
+
+pub struct HttpClient {
+    client: reqwest::Client,
+    options: HttpClientOptions,
+    signer: Option<Signer>,
+    offline: bool,
+    host_limits: Mutex<HashMap<String, Arc<HostLimit>>>,
+    /// Slots of requests in flight over all hosts, shared by all tasks.
+    request_slots: Semaphore,
+}
+
+/**
+ * Politeness state per host: in-flight requests and the earliest time of the next request.
+ */
+struct HostLimit {
+    semaphore: Arc<Semaphore>,
+    next_request_at: tokio::sync::Mutex<Instant>,
+}
+
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    pub url: Url,
+    pub redirected_from: Vec<Url>,
+}
+
+enum Attempt {
+    Done(StatusCode, HeaderMap, Vec<u8>),
+    Retry(String, Option<Duration>),
+}
+
+impl HttpClient {
+    pub fn new(
+        options: HttpClientOptions,
+        concurrency: usize,
+        signer: Option<Signer>,
+    ) -> Result<Self, Box<dyn Error>> {
+        if options.max_requests_per_host == 0 {
+            return Err("Max requests per host should be positive.".into());
+        }
+        if concurrency == 0 {
+            return Err("Concurrency should be positive.".into());
+        }
+
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(options.request_timeout)
+            .build()?;
+
+        Ok(Self {
+            client,
+            options,
+            signer,
+            offline: false,
+            host_limits: Mutex::new(HashMap::new()),
+            request_slots: Semaphore::new(concurrency),
+        })
+    }
+
+    /**
+     * A client which refuses any requests.
+     */
+    pub fn offline() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            options: HttpClientOptions {
+                redirect_policy: RedirectPolicy::None,
+                max_redirects: 0,
+                max_requests_per_host: 1,
+                min_request_interval: Duration::ZERO,
+                max_retries: 0,
+                initial_retry_backoff: Duration::ZERO,
+                max_retry_backoff: Duration::ZERO,
+                request_timeout: Duration::ZERO,
+            },
+            signer: None,
+            offline: true,
+            host_limits: Mutex::new(HashMap::new()),
+            request_slots: Semaphore::new(1),
+        }
+    }
+
+    pub fn signer(&self) -> Option<&Signer> {
+        self.signer.as_ref()
+    }
+
+    pub async fn get(&self, uri: &str, accept: Option<&str>) -> Result<Response, Box<dyn Error>> {
+        if self.offline {
+            return Err(format!("Network access is disabled: url={uri}").into());
+        }
+
+        let mut current_url = Url::parse(uri)?;
+        let mut redirected_from: Vec<Url> = vec![];
+        loop {
+            let (status, headers, body) = self.get_with_retries(&current_url, accept).await?;
+
+            match status {
+                x @ (StatusCode::MOVED_PERMANENTLY
+                | StatusCode::FOUND
+                | StatusCode::SEE_OTHER
+                | StatusCode::TEMPORARY_REDIRECT
+                | StatusCode::PERMANENT_REDIRECT) => {
+                    let location = match headers
+                        .get(reqwest::header::LOCATION)
+                        .and_then(|x| x.to_str().ok())
+                    {
+                        None => {
+                            return Err(format!(
+                                "Redirection without location: status={x}, url={current_url}"
+                            )
+                            .into())
+                        }
+                        Some(x) => current_url.join(x)?,
+                    };
+
+                    self.check_redirection(&current_url, &location, &redirected_from)?;
+
+                    redirected_from.push(current_url);
+                    current_url = location;
+                }
+                _ => {
+                    return Ok(Response {
+                        status,
+                        headers,
+                        body,
+                        url: current_url,
+                        redirected_from,
+                    })
+                }
+            }
+        }
+    }
+
+    async fn get_with_retries(
+        &self,
+        url: &Url,
+        accept: Option<&str>,
+    ) -> Result<(StatusCode, HeaderMap, Vec<u8>), Box<dyn Error>> {
+        let mut retries: usize = 0;
+        loop {
+            let permit = self.wait_host_slot(url).await?;
+            let request_permit = self.request_slots.acquire().await?;
+            let attempt = self.attempt(url, accept).await?;
+            drop(request_permit);
+            drop(permit);
+
+            match attempt {
+                Attempt::Done(status, headers, body) => return Ok((status, headers, body)),
+                Attempt::Retry(reason, retry_after_opt) => {
+                    if retries >= self.options.max_retries {
+                        return Err(HttpError::Transient {
+                            url: url.clone(),
+                            reason: format!("{reason} (retried {retries} times)"),
+                        }
+                        .into());
+                    }
+
+                    // Retry-After of servers is respected as it is, and only the exponential
+                    // backoff is capped.
+                    let backoff = match retry_after_opt {
+                        Some(retry_after) if retry_after > self.options.max_retry_backoff => {
+                            return Err(HttpError::Transient {
+                                url: url.clone(),
+                                reason: format!(
+                                    "{reason} (Retry-After exceeds max backoff: retry_after={}ms)",
+                                    retry_after.as_millis()
+                                ),
+                            }
+                            .into());
+                        }
+                        Some(retry_after) => retry_after,
+                        None => self
+                            .options
+                            .initial_retry_backoff
+                            .saturating_mul(1 << retries.min(16))
+                            .min(self.options.max_retry_backoff),
+                    };
+                    eprintln!(
+                        "Info: Retry a request after {}ms: {reason}, url={url}",
+                        backoff.as_millis()
+                    );
+                    self.defer_host(url, backoff).await?;
+                    retries += 1;
+                }
+            }
+        }
+    }
+
+    async fn attempt(&self, url: &Url, accept: Option<&str>) -> Result<Attempt, Box<dyn Error>> {
+        let mut request = self.client.get(url.clone());
+        if let Some(accept) = accept {
+            request = request.header(reqwest::header::ACCEPT, accept);
+        }
+        if let Some(signer) = &self.signer {
+            let signed_headers = signer.sign_get(url)?;
+            request = request
+                .header(reqwest::header::HOST, signed_headers.host)
+                .header(reqwest::header::DATE, signed_headers.date)
+                .header("Signature", signed_headers.signature);
+        }
+
+        let response = match request.send().await {
+            Ok(x) => x,
+            Err(err) if is_transient_reqwest_error(&err) => {
+                return Ok(Attempt::Retry(format!("{err}"), None))
+            }
+            Err(err) => {
+                return Err(HttpError::Permanent {
+                    url: url.clone(),
+                    reason: format!("{err}"),
+                }
+                .into())
+            }
+        };
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        if is_transient_status(status) {
+            return Ok(Attempt::Retry(
+                format!("status={status}"),
+                retry_after(&headers),
+            ));
+        }
+
+        match response.bytes().await {
+            Ok(body) => Ok(Attempt::Done(status, headers, body.to_vec())),
+            Err(err) if is_transient_reqwest_error(&err) => {
+                Ok(Attempt::Retry(format!("{err}"), None))
+            }
+            Err(err) => Err(HttpError::Permanent {
+                url: url.clone(),
+                reason: format!("{err}"),
+            }
+            .into()),
+        }
+    }
+
+    fn host_limit(&self, url: &Url) -> Result<Arc<HostLimit>, Box<dyn Error>> {
+        let host_key = format!(
+            "{}:{}",
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or_default()
+        );
+        match self.host_limits.lock() {
+            Err(_) => Err("Host limits are poisoned.".into()),
+            Ok(mut host_limits) => Ok(host_limits
+                .entry(host_key)
+                .or_insert_with(|| {
+                    Arc::new(HostLimit {
+                        semaphore: Arc::new(Semaphore::new(self.options.max_requests_per_host)),
+                        next_request_at: tokio::sync::Mutex::new(Instant::now()),
+                    })
+                })
+                .clone()),
+        }
+    }
+
+    async fn wait_host_slot(&self, url: &Url) -> Result<OwnedSemaphorePermit, Box<dyn Error>> {
+        let host_limit = self.host_limit(url)?;
+
+        let permit = host_limit.semaphore.clone().acquire_owned().await?;
+
+        let mut next_request_at = host_limit.next_request_at.lock().await;
+        tokio::time::sleep_until(*next_request_at).await;
+        *next_request_at = Instant::now() + self.options.min_request_interval;
+
+        Ok(permit)
+    }
+
+    /**
+     * Hold off all requests to the host, since the host is likely overloaded.
+     */
+    async fn defer_host(&self, url: &Url, delay: Duration) -> Result<(), Box<dyn Error>> {
+        let host_limit = self.host_limit(url)?;
+
+        let mut next_request_at = host_limit.next_request_at.lock().await;
+        let deferred_at = Instant::now() + delay;
+        if *next_request_at < deferred_at {
+            *next_request_at = deferred_at;
+        }
+
+        Ok(())
+    }
+
+    fn check_redirection(
+        &self,
+        from: &Url,
+        to: &Url,
+        redirected_from: &[Url],
+    ) -> Result<(), Box<dyn Error>> {
+        match self.options.redirect_policy {
+            RedirectPolicy::None => {
+                return Err(format!("Redirection is not allowed: from={from}, to={to}").into());
+            }
+            RedirectPolicy::SameOrigin => {
+                if from.origin() != to.origin() {
+                    return Err(format!(
+                        "Redirection to another origin is not allowed: from={from}, to={to}"
+                    )
+                    .into());
+                }
+            }
+            RedirectPolicy::Any => {
+                // do nothing
+            }
+        }
+
+        if redirected_from.len() >= self.options.max_redirects {
+            return Err(format!(
+                "Too many redirections: max={}, from={from}, to={to}",
+                self.options.max_redirects
+            )
+            .into());
+        }
+
+        if to == from || redirected_from.contains(to) {
+            return Err(format!("Redirection loop is detected: from={from}, to={to}").into());
+        }
+
+        Ok(())
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::REQUEST_TIMEOUT
+            | StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+fn is_transient_reqwest_error(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect() || err.is_request() || err.is_body()
+}
+
+/**
+ * Parse `Retry-After` as either delay seconds or HTTP date.
+ */
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
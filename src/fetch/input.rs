@@ -7,7 +7,35 @@ pub struct Input {
     pub title: Option<String>,
     pub description: Option<String>,
     pub static_base_url: String,
+    #[serde(default)]
     pub accounts: Vec<String>,
+    #[serde(default)]
+    pub mastodon_exports: Vec<MastodonExportInput>,
+    #[serde(default)]
+    pub misskey_exports: Vec<MisskeyExportInput>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MastodonExportInput {
+    /// A path of the zip file or the extracted directory.
+    pub path: String,
+    /// An account like `@user@example.com`, which is guessed from the actor if missing.
+    pub account: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MisskeyExportInput {
+    /// A path of the exported notes JSON.
+    pub notes_path: String,
+    /// A path of the exported drive files as a zip file or an extracted directory.
+    /// Files are looked up by their IDs such as `<id>.png`, or by their names if unique.
+    pub drive_path: Option<String>,
+    /// An account like `@user@example.com`.
+    pub account: String,
+    /// An internal ID of the user, which is used for the actor ID `https://example.com/users/<id>`.
+    pub user_id: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
 }
 
 pub async fn load(path: &str) -> Result<Input, Box<dyn Error>> {
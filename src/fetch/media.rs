@@ -0,0 +1,60 @@
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+use url::Url;
+
+use super::http::{HttpClient, HttpError};
+
+pub struct Media {
+    pub content_type: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+pub async fn fetch_media(client: &HttpClient, uri: &str) -> Result<Media, Box<dyn Error>> {
+    let response = client.get(uri, None).await?;
+    match response.status {
+        StatusCode::OK => {
+            // continue
+        }
+        x => {
+            return Err(HttpError::Permanent {
+                url: response.url,
+                reason: format!("Unknown response: status={x}"),
+            }
+            .into())
+        }
+    }
+
+    let content_type = response
+        .headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .map(|x| x.to_string());
+
+    Ok(Media {
+        content_type,
+        bytes: response.body,
+    })
+}
+
+/**
+ * Media mirrored in this run keyed by their original URLs, not to fetch the same media again.
+ *
+ * A mirrored URL is `None` if the media is not available.
+ */
+#[derive(Default)]
+pub struct MirroredMedia {
+    urls: Mutex<HashMap<String, Arc<OnceCell<Option<Url>>>>>,
+}
+
+impl MirroredMedia {
+    pub fn entry(&self, original_url: &str) -> Arc<OnceCell<Option<Url>>> {
+        let mut urls = match self.urls.lock() {
+            Ok(urls) => urls,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        urls.entry(original_url.to_string()).or_default().clone()
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use activitist::model as ap_model;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+const AS_PUBLIC: &str = "https://www.w3.org/ns/activitystreams#Public";
+const MISSKEY_QUOTE_REL: &str = "https://misskey-hub.net/ns#_misskey_quote";
+
+/**
+ * An item of the notes export of Misskey and its forks.
+ */
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Note {
+    pub id: String,
+    pub text: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub cw: Option<String>,
+    #[serde(default)]
+    pub files: Vec<DriveFile>,
+    pub reply_id: Option<String>,
+    pub renote_id: Option<String>,
+    pub poll: Option<Poll>,
+    pub visibility: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DriveFile {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub typ: Option<String>,
+    pub url: Option<String>,
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub is_sensitive: bool,
+}
+
+impl DriveFile {
+    /**
+     * Names of the file in drive exports, which are looked up in order.
+     *
+     * Names given by users may collide, so they are used only if `name_is_unique`.
+     */
+    pub fn export_names(&self, name_is_unique: bool) -> Vec<String> {
+        let mut names = vec![self.id.clone()];
+        if let Some(ext) = Path::new(&self.name).extension().and_then(|x| x.to_str()) {
+            names.push(format!("{}.{ext}", self.id));
+        }
+        if name_is_unique {
+            names.push(self.name.clone());
+        }
+        names
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Poll {
+    pub choices: Vec<String>,
+    #[serde(default)]
+    pub multiple: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+pub struct NoteContext<'a> {
+    pub domain: &'a str,
+    pub actor_id: &'a str,
+    pub followers_id: &'a str,
+}
+
+impl<'a> NoteContext<'a> {
+    fn note_url(&self, id: &str) -> String {
+        format!("https://{}/notes/{id}", self.domain)
+    }
+}
+
+impl Note {
+    /**
+     * Only notes which are visible for anyone are archived.
+     */
+    pub fn is_public(&self) -> bool {
+        self.visibility == "public" || self.visibility == "home"
+    }
+
+    /**
+     * Renotes without any text nor files, which are not archived as same as announces of outboxes.
+     */
+    pub fn is_pure_renote(&self) -> bool {
+        self.renote_id.is_some() && self.text.is_none() && self.files.is_empty()
+    }
+
+    /**
+     * Converts to a `Create` activity. Pure renotes should be excluded beforehand.
+     */
+    pub fn to_activity(&self, ctx: &NoteContext) -> ap_model::Object {
+        let note_url = ctx.note_url(&self.id);
+        let (to, cc) = self.audience(ctx);
+
+        let mut activity = empty_object(format!("{note_url}/activity"), "Create");
+        activity.object_items.published = Some(self.created_at);
+        activity.object_items.to = to.clone();
+        activity.object_items.cc = cc.clone();
+        activity.activity_items.actor = vec![link(ctx.actor_id)];
+
+        let mut note = empty_object(
+            note_url.clone(),
+            if self.poll.is_some() {
+                "Question"
+            } else {
+                "Note"
+            },
+        );
+        note.object_items.url = Some(ap_model::Link::from(note_url.as_str()));
+        note.object_items.published = Some(self.created_at);
+        note.object_items.to = to;
+        note.object_items.cc = cc;
+        note.object_items.attributed_to = vec![link(ctx.actor_id)];
+        if let Some(reply_id) = &self.reply_id {
+            note.object_items.in_reply_to = vec![link(&ctx.note_url(reply_id))];
+        }
+
+        let mut content = match &self.text {
+            None => String::new(),
+            Some(text) => text_to_html(text),
+        };
+        if let Some(renote_id) = &self.renote_id {
+            let quote_url = ctx.note_url(renote_id);
+            content.push_str(&format!(
+                "<p><span class=\"quote-inline\">RE: <a href=\"{quote_url}\">{quote_url}</a></span></p>"
+            ));
+
+            // ref: https://codeberg.org/fediverse/fep/src/branch/main/fep/e232/fep-e232.md
+            let mut quote_link = ap_model::Link::from(quote_url.as_str());
+            quote_link.typ = vec!["Link".to_string()];
+            quote_link.rel = vec![MISSKEY_QUOTE_REL.to_string()];
+            quote_link.object_items.media_type = vec!["application/activity+json".to_string()];
+            note.object_items.tag = vec![ap_model::ObjectOrLink::Link(quote_link)];
+        }
+        note.object_items.content = vec![content];
+
+        if let Some(cw) = &self.cw {
+            note.object_items.summary = vec![cw.to_string()];
+            note.activity_streams_ext_items.sensitive = Some(true);
+        }
+        if self.files.iter().any(|x| x.is_sensitive) {
+            note.activity_streams_ext_items.sensitive = Some(true);
+        }
+
+        note.object_items.attachment = self
+            .files
+            .iter()
+            .filter_map(|file| {
+                let url = file.url.as_ref()?;
+                let mut document = empty_object_without_id("Document");
+                document.object_items.url = Some(ap_model::Link::from(url.as_str()));
+                document.object_items.media_type = file.typ.iter().cloned().collect();
+                document.object_items.name = file.comment.iter().cloned().collect();
+                Some(ap_model::ObjectOrLink::Object(document))
+            })
+            .collect();
+
+        if let Some(poll) = &self.poll {
+            let choices = poll
+                .choices
+                .iter()
+                .map(|choice| {
+                    let mut choice_object = empty_object_without_id("Note");
+                    choice_object.object_items.name = vec![choice.to_string()];
+                    ap_model::ObjectOrLink::Object(choice_object)
+                })
+                .collect();
+            if poll.multiple {
+                note.question_items.any_of = choices;
+            } else {
+                note.question_items.one_of = choices;
+            }
+            note.object_items.end_time = poll.expires_at;
+        }
+
+        activity.activity_items.object = vec![ap_model::ObjectOrLink::Object(note)];
+        activity
+    }
+
+    fn audience(
+        &self,
+        ctx: &NoteContext,
+    ) -> (Vec<ap_model::ObjectOrLink>, Vec<ap_model::ObjectOrLink>) {
+        if self.visibility == "home" {
+            (vec![link(ctx.followers_id)], vec![link(AS_PUBLIC)])
+        } else {
+            (vec![link(AS_PUBLIC)], vec![link(ctx.followers_id)])
+        }
+    }
+}
+
+pub struct Profile<'a> {
+    pub username: &'a str,
+    pub actor_id: &'a str,
+    pub profile_url: &'a str,
+    pub name: Option<&'a str>,
+    pub description: Option<&'a str>,
+}
+
+/**
+ * Notes exports do not contain profiles, so actors are made from given ones.
+ */
+pub fn to_actor(profile: &Profile) -> ap_model::Object {
+    let mut actor = empty_object(profile.actor_id.to_string(), "Person");
+    actor.object_items.url = Some(ap_model::Link::from(profile.profile_url));
+    actor.object_items.name = profile.name.iter().map(|x| x.to_string()).collect();
+    actor.object_items.summary = profile
+        .description
+        .iter()
+        .map(|x| text_to_html(x))
+        .collect();
+    actor.actor_items = Some(ap_model::ActorItems {
+        // They are replaced with archived ones on saving.
+        inbox: String::new(),
+        outbox: String::new(),
+        following: String::new(),
+        followers: String::new(),
+        preferred_username: Some(profile.username.to_string()),
+        endpoints: HashMap::new(),
+    });
+    actor
+}
+
+fn text_to_html(text: &str) -> String {
+    text.split("\n\n")
+        .map(|paragraph| {
+            let lines: Vec<String> = paragraph.lines().map(handlebars::html_escape).collect();
+            format!("<p>{}</p>", lines.join("<br>"))
+        })
+        .collect()
+}
+
+fn link(href: &str) -> ap_model::ObjectOrLink {
+    ap_model::ObjectOrLink::Link(ap_model::Link::from(href))
+}
+
+fn empty_object(id: String, typ: &str) -> ap_model::Object {
+    let mut object = empty_object_without_id(typ);
+    object.id = Some(id);
+    object
+}
+
+fn empty_object_without_id(typ: &str) -> ap_model::Object {
+    ap_model::Object {
+        schema_context: None,
+        id: None,
+        typ: vec![typ.to_string()],
+        object_items: ap_model::ObjectItems::empty(),
+        actor_items: None,
+        activity_items: ap_model::ActivityItems::empty(),
+        collection_items: ap_model::CollectionItems::empty(),
+        ordered_collection_items: ap_model::OrderedCollectionItems::empty(),
+        collection_page_items: ap_model::CollectionPageItems::empty(),
+        ordered_collection_page_items: ap_model::OrderedCollectionPageItems::empty(),
+        relationship_items: ap_model::RelationshipItems::empty(),
+        tombstone_items: ap_model::TombstoneItems::empty(),
+        question_items: ap_model::QuestionItems::empty(),
+        place_items: ap_model::PlaceItems::empty(),
+        activity_streams_ext_items: ap_model::ActivityStreamExtItems::empty(),
+        mastodon_ext_items: ap_model::MastodonExtItems::empty(),
+        security_items: ap_model::SecurityItems::empty(),
+        property_items: ap_model::PropertyItems::empty(),
+    }
+}
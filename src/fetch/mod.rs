@@ -1,67 +1,240 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 mod activitypub;
+mod crawl_state;
 mod env;
+mod export_archive;
+mod http;
 mod input;
+mod media;
+pub mod misskey_export;
+pub mod object_path;
 mod output;
+mod report;
+mod signature;
 mod templates;
 mod webfinger;
 
-use activitist::json::SerdeJsonValue;
+use crate::helper::url_helper::FullUrl;
+use crate::manifest::{Manifest, ManifestAccount};
+use crate::webfinger::resource::{Link as WebfingerLink, Resource as WebfingerResource};
+use activitist::json::{JsonSerde, SerdeJsonValue};
 use activitist::model as ap_model;
-use archivedon::helper::url_helper::FullUrl;
-use archivedon::redirect_map::RedirectMap;
-use archivedon::webfinger::resource::{Link as WebfingerLink, Resource as WebfingerResource};
 use chrono::Utc;
+use futures_util::{stream, StreamExt};
 use once_cell::sync::Lazy;
 use output::Output;
 use regex::Regex;
 use serde_json::json;
+use sha2::{Digest, Sha256};
 use url::Url;
 
+use self::crawl_state::{ArchivedItem, CrawlState, FailedItem};
 use self::env::Env;
+use self::export_archive::ExportArchive;
+use self::http::HttpClient;
+pub use self::http::{HttpClientOptions, RedirectPolicy};
+use self::input::{Input, MastodonExportInput, MisskeyExportInput};
+use self::media::MirroredMedia;
+use self::object_path::ObjectPathResolver;
+pub use self::object_path::ObjectPathRuleKind;
+use self::report::Reporter;
+use self::signature::Signer;
 use self::templates::{ObjectHtmlParams, ProfileHtmlParams, Templates, TopHtmlParams};
 
+pub struct Options {
+    pub fetch_outbox: bool,
+    pub fetch_media: bool,
+    pub default_max_pages: usize,
+    pub page_items_count: usize,
+    pub http: HttpClientOptions,
+    pub signing_key_path: Option<String>,
+    pub object_path_rules: Vec<ObjectPathRuleKind>,
+    pub concurrency: usize,
+    pub use_crawl_state: bool,
+    pub continue_on_error: bool,
+    pub report_path: Option<String>,
+    pub precompress: bool,
+}
+
+const INSTANCE_ACTOR_PATH: &str = "predef/instance-actor.json";
+
 pub async fn run(
     input_path: &str,
     output_path: &str,
-    fetch_outbox: bool,
-    default_max_pages: usize,
-    page_items_count: usize,
+    options: Options,
 ) -> Result<(), Box<dyn Error>> {
     let input = input::load(input_path).await?;
+    let report_path = options.report_path.as_deref();
+    let static_base_url = Url::parse(&input.static_base_url)?;
+
+    let signer = match &options.signing_key_path {
+        None => None,
+        Some(signing_key_path) => {
+            let mut key_id = static_base_url.join(INSTANCE_ACTOR_PATH)?;
+            key_id.set_fragment(Some("main-key"));
+            Some(Signer::load(signing_key_path, key_id.to_string()).await?)
+        }
+    };
 
     let env = Env {
-        client: reqwest::Client::new(),
-        output: Output::load(Path::new(output_path)).await?,
+        client: HttpClient::new(options.http, options.concurrency, signer)?,
+        output: Output::load(Path::new(output_path), options.precompress).await?,
         templates: Templates::create()?,
-        default_max_pages,
-        static_base_url: Url::parse(&input.static_base_url)?,
-        fetch_outbox,
-        page_items_count,
+        default_max_pages: options.default_max_pages,
+        static_base_url,
+        fetch_outbox: options.fetch_outbox,
+        fetch_media: options.fetch_media,
+        mirrored_media: MirroredMedia::default(),
+        page_items_count: options.page_items_count,
+        object_path_resolver: ObjectPathResolver::from_kinds(&options.object_path_rules),
+        concurrency: options.concurrency,
+        use_crawl_state: options.use_crawl_state,
+        continue_on_error: options.continue_on_error,
+        reporter: Arc::new(Reporter::new()),
     };
 
     let predef_urls = save_predefs(&env).await?;
 
     env.output
         .save_top_page(&env.templates.render_top_html(&TopHtmlParams {
-            title: match input.title {
-                Some(title) => title,
+            title: match &input.title {
+                Some(title) => title.clone(),
                 None => "Archived ActivityPub Server".to_string(),
             },
-            description: match input.description {
-                Some(description) => description,
+            description: match &input.description {
+                Some(description) => description.clone(),
                 None => "A hub of archived ActivityPub servers.".to_string(),
             },
         })?)
         .await?;
 
-    for account in input.accounts {
-        fetch_account(&env, &predef_urls, &account).await?;
+    let offline_env_opt = if !input.mastodon_exports.is_empty() || !input.misskey_exports.is_empty()
+    {
+        // Exports are imported without any network access.
+        Some(Env {
+            client: HttpClient::offline(),
+            output: Output::load(Path::new(output_path), options.precompress).await?,
+            templates: Templates::create()?,
+            default_max_pages: env.default_max_pages,
+            static_base_url: env.static_base_url.clone(),
+            fetch_outbox: true,
+            fetch_media: false,
+            mirrored_media: MirroredMedia::default(),
+            page_items_count: env.page_items_count,
+            object_path_resolver: ObjectPathResolver::from_kinds(&options.object_path_rules),
+            concurrency: env.concurrency,
+            // Exports are imported as a whole, since they may be changed between runs.
+            use_crawl_state: false,
+            continue_on_error: env.continue_on_error,
+            reporter: env.reporter.clone(),
+        })
+    } else {
+        None
+    };
+
+    let mut manifest = match env.output.load_manifest().await? {
+        None => Manifest::new(Utc::now()),
+        Some(x) => x,
+    };
+    let result = archive_accounts(
+        &env,
+        offline_env_opt.as_ref(),
+        &predef_urls,
+        &input,
+        &mut manifest,
+    )
+    .await;
+
+    // Accounts finished so far are saved even if the run is aborted.
+    manifest.updated_at = Utc::now();
+    env.output.save_manifest(&manifest).await?;
+
+    save_report(&env, report_path).await?;
+
+    result
+}
+
+async fn archive_accounts<'a>(
+    env: &Env<'a>,
+    offline_env_opt: Option<&Env<'a>>,
+    predef_urls: &PredefUrls,
+    input: &Input,
+    manifest: &mut Manifest,
+) -> Result<(), Box<dyn Error>> {
+    // Requests are limited by `concurrency` over all tasks in the client,
+    // so this and nested streams only bound tasks in progress.
+    let mut fetch_account_results = stream::iter(&input.accounts)
+        .map(|account| async move { (account, fetch_account(env, predef_urls, account).await) })
+        .buffered(env.concurrency);
+    while let Some((account, result)) = fetch_account_results.next().await {
+        match result {
+            Ok(manifest_account) => manifest.upsert_account(manifest_account),
+            Err(err) => handle_account_failure(env, &report_account_key(env, account), err)?,
+        }
+    }
+
+    let offline_env = match offline_env_opt {
+        None => return Ok(()),
+        Some(x) => x,
+    };
+
+    for export in &input.mastodon_exports {
+        match import_mastodon_export(offline_env, predef_urls, export).await {
+            Ok(manifest_account) => manifest.upsert_account(manifest_account),
+            Err(err) => {
+                let account_key = match &export.account {
+                    None => export.path.clone(),
+                    Some(account) => report_account_key(env, account),
+                };
+                handle_account_failure(env, &account_key, err)?;
+            }
+        }
+    }
+
+    for export in &input.misskey_exports {
+        match import_misskey_export(offline_env, predef_urls, export).await {
+            Ok(manifest_account) => manifest.upsert_account(manifest_account),
+            Err(err) => {
+                let account_key = report_account_key(env, &export.account);
+                handle_account_failure(env, &account_key, err)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn report_account_key(env: &Env, account: &str) -> String {
+    match Account::parse(account, &env.static_base_url) {
+        Ok(account) => account.ident,
+        Err(_) => account.to_string(),
     }
+}
+
+fn handle_account_failure(
+    env: &Env,
+    account_key: &str,
+    err: Box<dyn Error>,
+) -> Result<(), Box<dyn Error>> {
+    env.reporter
+        .record_account_failure(account_key, err.as_ref());
+    if !env.continue_on_error {
+        return Err(err);
+    }
+
+    println!("Warning: Failed to archive an account: account={account_key}, err={err}");
+    Ok(())
+}
 
+async fn save_report<'a>(env: &Env<'a>, report_path: Option<&str>) -> Result<(), Box<dyn Error>> {
+    if let Some(report_path) = report_path {
+        tokio::fs::write(report_path, serde_json::to_vec(&env.reporter.report())?).await?;
+    }
     Ok(())
 }
 
@@ -100,24 +273,294 @@ impl Account {
             profile_url: static_base_url.join(&profile_path)?,
         })
     }
+
+    fn parse(account: &str, static_base_url: &Url) -> Result<Account, Box<dyn Error>> {
+        let account_stripped = account.strip_prefix('@').unwrap_or(account);
+        match account_stripped.split_once('@') {
+            None => Err(format!("Illegal account: {account}").into()),
+            Some((username, domain)) => Account::new(username, domain, static_base_url),
+        }
+    }
 }
 
 async fn fetch_account<'a>(
     env: &Env<'a>,
     predef_urls: &PredefUrls,
     account: &str,
-) -> Result<(), Box<dyn Error>> {
-    let account_stripped = account.strip_prefix("@").unwrap_or(&account);
-    let account = match account_stripped.split_once("@") {
-        None => return Err(format!("Illegal account: {account}").into()),
-        Some((username, domain)) => Account::new(username, domain, &env.static_base_url)?,
-    };
+) -> Result<ManifestAccount, Box<dyn Error>> {
+    let account = Account::parse(account, &env.static_base_url)?;
+    env.reporter.record_account(&account.ident);
 
     let subject = format!("acct:{}", account.ident);
 
     let account_actor_url =
         webfinger::fetch_ap_account_actor_url(&env.client, &account.domain, &subject).await?;
-    let account_actor = activitypub::fetch_actor(&env.client, account_actor_url).await?;
+    let fetched_account_actor = activitypub::fetch_actor(&env.client, account_actor_url).await?;
+
+    let outbox_ref_opt = if env.fetch_outbox {
+        fetched_account_actor
+            .data
+            .actor_items
+            .as_ref()
+            .map(|actor_items| {
+                ap_model::ObjectOrLink::Link(ap_model::Link::from(actor_items.outbox.as_str()))
+            })
+    } else {
+        None
+    };
+
+    archive_account(
+        env,
+        predef_urls,
+        &account,
+        fetched_account_actor.data,
+        outbox_ref_opt.as_ref(),
+        &fetched_account_actor.redirected_from,
+    )
+    .await
+}
+
+async fn import_mastodon_export<'a>(
+    env: &Env<'a>,
+    predef_urls: &PredefUrls,
+    export_input: &MastodonExportInput,
+) -> Result<ManifestAccount, Box<dyn Error>> {
+    let export = ExportArchive::open(Path::new(&export_input.path)).await?;
+
+    let mut account_actor = ap_model::Object::from_json_bytes(&export.read("actor.json").await?)?;
+    let mut outbox = ap_model::Object::from_json_bytes(&export.read("outbox.json").await?)?;
+
+    let account = match &export_input.account {
+        Some(account) => Account::parse(account, &env.static_base_url)?,
+        None => {
+            let username = match account_actor
+                .actor_items
+                .as_ref()
+                .and_then(|x| x.preferred_username.as_ref())
+            {
+                None => return Err("The username of actor is not available.".into()),
+                Some(x) => x,
+            };
+            let actor_id = match &account_actor.id {
+                None => return Err("ID of actor should be available.".into()),
+                Some(x) => FullUrl::parse(x)?,
+            };
+            Account::new(username, actor_id.domain(), &env.static_base_url)?
+        }
+    };
+    env.reporter.record_account(&account.ident);
+    if account_actor.actor_items.is_none() {
+        return Err("Actor items are should be available.".into());
+    }
+
+    import_export_media(env, &account, &export, &mut account_actor).await?;
+    for activity in outbox
+        .collection_items
+        .items
+        .iter_mut()
+        .chain(outbox.ordered_collection_items.ordered_items.iter_mut())
+    {
+        if let ap_model::ObjectOrLink::Object(activity) = activity {
+            for object in &mut activity.activity_items.object {
+                if let ap_model::ObjectOrLink::Object(object) = object {
+                    import_export_media(env, &account, &export, object).await?;
+                }
+            }
+        }
+    }
+
+    archive_account(
+        env,
+        predef_urls,
+        &account,
+        account_actor,
+        Some(&ap_model::ObjectOrLink::Object(outbox)),
+        &[],
+    )
+    .await
+}
+
+async fn import_export_media<'a>(
+    env: &Env<'a>,
+    account: &Account,
+    export: &ExportArchive,
+    object: &mut ap_model::Object,
+) -> Result<(), Box<dyn Error>> {
+    let object_items = &mut object.object_items;
+    for media_ref in object_items
+        .attachment
+        .iter_mut()
+        .chain(object_items.icon.iter_mut())
+        .chain(object_items.image.iter_mut())
+    {
+        let Some(link) = media_link_mut(media_ref) else {
+            continue;
+        };
+
+        // Only media with relative paths are contained in the export.
+        if Url::parse(&link.href).is_ok() {
+            continue;
+        }
+
+        let bytes = match export.read(&link.href).await {
+            Ok(x) => x,
+            Err(err) => {
+                println!(
+                    "Warning: Media is not found in the export: path={}, err={err}",
+                    link.href
+                );
+                continue;
+            }
+        };
+
+        let new_url = save_imported_media(
+            env,
+            account,
+            &link.href,
+            media_ext(&link.href, None).as_deref(),
+            &bytes,
+        )
+        .await?;
+        link.href = new_url.to_string();
+    }
+
+    Ok(())
+}
+
+async fn import_misskey_export<'a>(
+    env: &Env<'a>,
+    predef_urls: &PredefUrls,
+    export_input: &MisskeyExportInput,
+) -> Result<ManifestAccount, Box<dyn Error>> {
+    let account = Account::parse(&export_input.account, &env.static_base_url)?;
+    env.reporter.record_account(&account.ident);
+
+    let mut notes: Vec<misskey_export::Note> =
+        serde_json::from_slice(&tokio::fs::read(&export_input.notes_path).await?)?;
+    notes.retain(|note| note.is_public());
+    let renotes_count = notes.iter().filter(|note| note.is_pure_renote()).count();
+    if renotes_count > 0 {
+        println!(
+            "Info: Skip pure renotes, which are not archived: account={}, count={renotes_count}",
+            account.ident
+        );
+        notes.retain(|note| !note.is_pure_renote());
+        for _ in 0..renotes_count {
+            env.reporter.record_skipped(&account.ident);
+        }
+    }
+    // Outboxes are ordered from newer items.
+    notes.sort_by_key(|note| std::cmp::Reverse(note.created_at));
+
+    if let Some(drive_path) = &export_input.drive_path {
+        let drive = ExportArchive::open(Path::new(drive_path)).await?;
+        let mut ids_by_name: HashMap<String, HashSet<String>> = HashMap::new();
+        for file in notes.iter().flat_map(|note| note.files.iter()) {
+            ids_by_name
+                .entry(file.name.clone())
+                .or_default()
+                .insert(file.id.clone());
+        }
+        for file in notes.iter_mut().flat_map(|note| note.files.iter_mut()) {
+            let name_is_unique = ids_by_name.get(&file.name).is_none_or(|x| x.len() == 1);
+            let mut bytes_opt = None;
+            for name in file.export_names(name_is_unique) {
+                if let Ok(bytes) = drive.read(&name).await {
+                    bytes_opt = Some(bytes);
+                    break;
+                }
+            }
+            let bytes = match bytes_opt {
+                Some(x) => x,
+                None => {
+                    println!(
+                        "Warning: File is not found in the drive export: id={}, name={}, name_is_unique={name_is_unique}",
+                        file.id, file.name
+                    );
+                    continue;
+                }
+            };
+            let original = file.url.clone().unwrap_or_else(|| file.id.to_string());
+            let new_url = save_imported_media(
+                env,
+                &account,
+                &original,
+                media_ext(&file.name, file.typ.as_deref()).as_deref(),
+                &bytes,
+            )
+            .await?;
+            file.url = Some(new_url.to_string());
+        }
+    }
+
+    let actor_id = format!("https://{}/users/{}", account.domain, export_input.user_id);
+    let followers_id = format!("{actor_id}/followers");
+    let note_context = misskey_export::NoteContext {
+        domain: &account.domain,
+        actor_id: &actor_id,
+        followers_id: &followers_id,
+    };
+
+    let outbox = ap_model::Object::new_collection(
+        None,
+        vec!["OrderedCollection".to_string()],
+        Some(notes.len()),
+        None,
+        None,
+        None,
+        vec![],
+        notes
+            .iter()
+            .map(|note| ap_model::ObjectOrLink::Object(note.to_activity(&note_context)))
+            .collect(),
+    );
+
+    let (username, _) = account
+        .ident
+        .split_once('@')
+        .unwrap_or((&account.ident, ""));
+    let account_actor = misskey_export::to_actor(&misskey_export::Profile {
+        username,
+        actor_id: &actor_id,
+        profile_url: &format!("https://{}/@{username}", account.domain),
+        name: export_input.name.as_deref(),
+        description: export_input.description.as_deref(),
+    });
+
+    archive_account(
+        env,
+        predef_urls,
+        &account,
+        account_actor,
+        Some(&ap_model::ObjectOrLink::Object(outbox)),
+        &[],
+    )
+    .await
+}
+
+async fn save_imported_media<'a>(
+    env: &Env<'a>,
+    account: &Account,
+    original: &str,
+    ext: Option<&str>,
+    bytes: &[u8],
+) -> Result<Url, Box<dyn Error>> {
+    let save_path = media_save_path(&account.domain, original, ext);
+    env.output
+        .save_static_bytes_resource(&save_path, bytes)
+        .await?;
+    Ok(env.static_base_url.join(&save_path)?)
+}
+
+async fn archive_account<'a>(
+    env: &Env<'a>,
+    predef_urls: &PredefUrls,
+    account: &Account,
+    mut account_actor: ap_model::Object,
+    outbox_ref_opt: Option<&ap_model::ObjectOrLink>,
+    redirected_from: &[Url],
+) -> Result<ManifestAccount, Box<dyn Error>> {
+    let subject = format!("acct:{}", account.ident);
 
     if !account_actor
         .mastodon_ext_items
@@ -131,43 +574,59 @@ async fn fetch_account<'a>(
         );
     }
 
+    let mut original_aliases = vec![];
+    if let Some(actor_id) = &account_actor.id {
+        original_aliases.push(actor_id.clone());
+    }
+    if let Some(link) = &account_actor.object_items.url {
+        original_aliases.push(link.href.to_string());
+    }
+    for url in redirected_from {
+        original_aliases.push(url.to_string());
+    }
+
     save_webfinger_resource(
         &env.output,
         subject,
         &account.actor_url,
         &account.profile_url,
+        &original_aliases,
     )
     .await?;
 
-    let outbox_url_opt = if env.fetch_outbox {
-        match &account_actor.actor_items {
-            None => None,
-            Some(actor_items) => Some(
-                fetch_outbox_collection_ref(
-                    env,
-                    &account,
-                    &ap_model::ObjectOrLink::Link(ap_model::Link::from(
-                        actor_items.outbox.as_str(),
-                    )),
-                )
-                .await?,
-            ),
-        }
-    } else {
-        None
+    let new_outbox_opt = match outbox_ref_opt {
+        None => None,
+        Some(outbox_ref) => Some(fetch_outbox_collection_ref(env, account, outbox_ref).await?),
+    };
+    let manifest_account = ManifestAccount {
+        account: account.ident.clone(),
+        actor_url: account.actor_url.to_string(),
+        posts_count: match &new_outbox_opt {
+            Some(new_outbox) => new_outbox.total_items_count,
+            // Keep the count of the previous run, since the outbox is not fetched.
+            None => env
+                .output
+                .load_manifest()
+                .await?
+                .and_then(|manifest| manifest.account(&account.ident).map(|x| x.posts_count))
+                .unwrap_or(0),
+        },
+        archived_at: Utc::now(),
     };
 
-    save_profile_resource(&env.output, &account, &account_actor, &env.templates).await?;
+    mirror_object_media(env, &mut account_actor).await?;
+
+    save_profile_resource(&env.output, account, &account_actor, &env.templates).await?;
 
     let original_account_id_opt = account_actor.id.clone();
     let original_account_link_opt = account_actor.object_items.url.clone();
 
     save_actor_resource(
         &env.output,
-        &account,
+        account,
         account_actor,
         predef_urls,
-        outbox_url_opt,
+        new_outbox_opt.map(|new_outbox| new_outbox.url),
     )
     .await?;
 
@@ -189,6 +648,8 @@ async fn fetch_account<'a>(
         }
     }
 
+    save_redirected_from_maps(env, redirected_from, &account.actor_url).await?;
+
     if let Some(link) = original_account_link_opt {
         if let Ok(old_url) = FullUrl::parse(&link.href) {
             let media_type = if link.object_items.media_type.is_empty() {
@@ -207,7 +668,7 @@ async fn fetch_account<'a>(
         }
     }
 
-    Ok(())
+    Ok(manifest_account)
 }
 
 async fn save_webfinger_resource(
@@ -215,6 +676,7 @@ async fn save_webfinger_resource(
     subject: String,
     ap_resource_url: &Url,
     profile_url: &Url,
+    original_aliases: &[String],
 ) -> Result<(), Box<dyn Error>> {
     let new_resource = WebfingerResource {
         subject,
@@ -237,7 +699,9 @@ async fn save_webfinger_resource(
             },
         ]),
     };
-    output.save_webfinger_resource(&new_resource).await?;
+    output
+        .save_webfinger_resource(&new_resource, original_aliases)
+        .await?;
     Ok(())
 }
 
@@ -290,9 +754,63 @@ async fn save_predefs<'a>(env: &Env<'a>) -> Result<PredefUrls, Box<dyn Error>> {
         )
         .await?;
 
+    if let Some(signer) = env.client.signer() {
+        save_instance_actor(env, &predef_urls, signer).await?;
+    }
+
     Ok(predef_urls)
 }
 
+/**
+ * An actor which is referred from signatures of fetch requests.
+ */
+async fn save_instance_actor<'a>(
+    env: &Env<'a>,
+    predef_urls: &PredefUrls,
+    signer: &Signer,
+) -> Result<(), Box<dyn Error>> {
+    let actor_url = env.static_base_url.join(INSTANCE_ACTOR_PATH)?;
+
+    env.output
+        .save_static_json_resource(
+            INSTANCE_ACTOR_PATH,
+            &ap_model::Object {
+                schema_context: Some(ap_model::Context::object_default()),
+                id: Some(actor_url.to_string()),
+                typ: vec!["Application".to_string()],
+                object_items: ap_model::ObjectItems::empty(),
+                actor_items: Some(ap_model::ActorItems {
+                    inbox: predef_urls.inbox_url.to_string(),
+                    outbox: predef_urls.empty_ordered_collection_url.to_string(),
+                    following: predef_urls.empty_ordered_collection_url.to_string(),
+                    followers: predef_urls.empty_ordered_collection_url.to_string(),
+                    preferred_username: actor_url.domain().map(|x| x.to_string()),
+                    endpoints: HashMap::new(),
+                }),
+                activity_items: ap_model::ActivityItems::empty(),
+                collection_items: ap_model::CollectionItems::empty(),
+                ordered_collection_items: ap_model::OrderedCollectionItems::empty(),
+                collection_page_items: ap_model::CollectionPageItems::empty(),
+                ordered_collection_page_items: ap_model::OrderedCollectionPageItems::empty(),
+                relationship_items: ap_model::RelationshipItems::empty(),
+                tombstone_items: ap_model::TombstoneItems::empty(),
+                question_items: ap_model::QuestionItems::empty(),
+                place_items: ap_model::PlaceItems::empty(),
+                activity_streams_ext_items: ap_model::ActivityStreamExtItems::empty(),
+                mastodon_ext_items: ap_model::MastodonExtItems::empty(),
+                security_items: ap_model::SecurityItems {
+                    public_key: Some(ap_model::Key {
+                        id: signer.key_id().to_string(),
+                        owner: actor_url.to_string(),
+                        public_key_pem: Some(signer.public_key_pem()),
+                    }),
+                },
+                property_items: ap_model::PropertyItems::empty(),
+            },
+        )
+        .await
+}
+
 async fn save_profile_resource<'a>(
     output: &Output,
     account: &Account,
@@ -310,18 +828,22 @@ async fn save_profile_resource<'a>(
                 name_map: original_actor.object_items.name_map.clone(),
                 summary: original_actor.object_items.summary.first().cloned(),
                 summary_map: original_actor.object_items.summary_map.clone(),
-                url: match &original_actor.object_items.url {
-                    None => None,
-                    Some(item) => Some(item.href.to_string()),
-                },
+                url: original_actor
+                    .object_items
+                    .url
+                    .as_ref()
+                    .map(|item| item.href.to_string()),
+                icon: first_media_url(&original_actor.object_items.icon),
+                image: first_media_url(&original_actor.object_items.image),
                 moved_to: original_actor
                     .activity_streams_ext_items
                     .moved_to
                     .to_owned(),
-                published: match &original_actor.object_items.published {
-                    None => None,
-                    Some(item) => Some(item.to_rfc3339()),
-                },
+                published: original_actor
+                    .object_items
+                    .published
+                    .as_ref()
+                    .map(|item| item.to_rfc3339()),
             })?,
         )
         .await
@@ -521,15 +1043,22 @@ impl NewOutboxCollectionManager {
     }
 }
 
+struct NewOutbox {
+    url: Url,
+    total_items_count: usize,
+}
+
 async fn fetch_outbox_collection_ref<'a>(
     env: &Env<'a>,
     account: &Account,
     collection_ref: &ap_model::ObjectOrLink,
-) -> Result<Url, Box<dyn Error>> {
+) -> Result<NewOutbox, Box<dyn Error>> {
     match collection_ref {
         ap_model::ObjectOrLink::Link(collection_ref) => {
             let collection =
-                activitypub::fetch_object(&env.client, collection_ref.href.to_string()).await?;
+                activitypub::fetch_object(&env.client, collection_ref.href.to_string())
+                    .await?
+                    .data;
             fetch_outbox_collection(env, account, &collection).await
         }
         ap_model::ObjectOrLink::Object(collection) => {
@@ -538,29 +1067,172 @@ async fn fetch_outbox_collection_ref<'a>(
     }
 }
 
+/**
+ * How to treat activities which are already archived.
+ */
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OnKnownActivity {
+    /// Stop crawling, since older activities are already archived.
+    Stop,
+    /// Skip it, since it is archived by an interrupted run.
+    Skip,
+}
+
+struct OutboxCrawler {
+    manager: NewOutboxCollectionManager,
+    state: CrawlState,
+    /// Keys of archived activities by `ArchivedItem::key`.
+    known_activity_keys: HashSet<String>,
+    on_known_activity: OnKnownActivity,
+    stopped: bool,
+    save_progress: bool,
+    last_saved_at: Instant,
+}
+
+impl OutboxCrawler {
+    fn new(page_items_count: usize, save_progress: bool) -> Self {
+        Self {
+            manager: NewOutboxCollectionManager::new(page_items_count),
+            state: CrawlState::default(),
+            known_activity_keys: HashSet::new(),
+            on_known_activity: OnKnownActivity::Stop,
+            stopped: false,
+            save_progress,
+            last_saved_at: Instant::now(),
+        }
+    }
+
+    async fn add_activity<'a>(
+        &mut self,
+        env: &Env<'a>,
+        item: ArchivedItem,
+        activity: ap_model::Object,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(key) = item.key() {
+            self.known_activity_keys.insert(key.to_string());
+        }
+        let base_path = item.base_path.clone();
+        self.state.archived_items.push(item);
+        self.manager
+            .add_activity_and_save_if_needed(env, base_path, activity)
+            .await
+    }
+
+    /**
+     * Record a failed activity at the current position, to retry it on the next run.
+     */
+    fn add_failure(&mut self, activity_id: String) {
+        self.known_activity_keys.insert(activity_id.clone());
+        self.state.failed_items.push(FailedItem {
+            activity_id,
+            position: self.state.archived_items.len(),
+        });
+    }
+
+    fn is_known(&self, activity_ref: &ap_model::ObjectOrLink) -> bool {
+        activity_key(activity_ref).is_some_and(|key| self.known_activity_keys.contains(&key))
+    }
+
+    /**
+     * Record a page to resume from, and save the progress once in a while.
+     */
+    async fn set_resume_page<'a>(
+        &mut self,
+        env: &Env<'a>,
+        account: &Account,
+        page_url_opt: Option<String>,
+    ) -> Result<(), Box<dyn Error>> {
+        self.state.resume_page_url = page_url_opt;
+        if self.save_progress && self.last_saved_at.elapsed() >= CRAWL_STATE_SAVE_INTERVAL {
+            env.output
+                .save_crawl_state(&account.ident, &self.state)
+                .await?;
+            self.last_saved_at = Instant::now();
+        }
+        Ok(())
+    }
+}
+
+const CRAWL_STATE_SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+fn object_or_link_id(object_or_link: &ap_model::ObjectOrLink) -> Option<String> {
+    match object_or_link {
+        ap_model::ObjectOrLink::Link(link) => Some(link.href.to_string()),
+        ap_model::ObjectOrLink::Object(object) => object.id.clone(),
+    }
+}
+
+/**
+ * A hash of the activity embedded without its ID, which is stable over runs.
+ */
+fn activity_content_hash(activity_ref: &ap_model::ObjectOrLink) -> Option<String> {
+    match activity_ref {
+        ap_model::ObjectOrLink::Object(object) if object.id.is_none() => {
+            let content = object.to_json_bytes().ok()?;
+            Some(format!("sha256:{:x}", Sha256::digest(&content)))
+        }
+        _ => None,
+    }
+}
+
+fn activity_key(activity_ref: &ap_model::ObjectOrLink) -> Option<String> {
+    object_or_link_id(activity_ref).or_else(|| activity_content_hash(activity_ref))
+}
+
+/**
+ * Fetch the outbox with the previous crawl state:
+ *
+ * 1. fetch new activities until an archived activity is found,
+ * 2. replay the archived activities,
+ * 3. fetch the rest of activities from the page where the previous run is interrupted.
+ */
 async fn fetch_outbox_collection<'a>(
     env: &Env<'a>,
     account: &Account,
     collection: &ap_model::Object,
-) -> Result<Url, Box<dyn Error>> {
-    let mut new_outbox_collection_manager = NewOutboxCollectionManager::new(env.page_items_count);
+) -> Result<NewOutbox, Box<dyn Error>> {
+    let prev_state_opt: Option<CrawlState> = if env.use_crawl_state {
+        env.output.load_crawl_state(&account.ident).await?
+    } else {
+        None
+    };
 
-    for item in &collection.collection_items.items {
-        fetch_outbox_activity_ref(env, account, item, &mut new_outbox_collection_manager).await?;
+    let mut crawler = OutboxCrawler::new(env.page_items_count, prev_state_opt.is_none());
+    if let Some(prev_state) = &prev_state_opt {
+        // Failed activities are also known, since they are retried at their positions on replaying.
+        crawler.known_activity_keys = prev_state
+            .archived_items
+            .iter()
+            .filter_map(|item| item.key().map(|x| x.to_string()))
+            .chain(
+                prev_state
+                    .failed_items
+                    .iter()
+                    .map(|item| item.activity_id.clone()),
+            )
+            .collect();
     }
 
-    for item in &collection.ordered_collection_items.ordered_items {
-        fetch_outbox_activity_ref(env, account, item, &mut new_outbox_collection_manager).await?;
-    }
+    fetch_outbox_activity_refs(
+        env,
+        account,
+        collection
+            .collection_items
+            .items
+            .iter()
+            .chain(&collection.ordered_collection_items.ordered_items),
+        &mut crawler,
+    )
+    .await?;
 
-    if {
-        collection.collection_items.total_items != Some(0)
-            && collection.collection_items.items.is_empty()
-            && collection.ordered_collection_items.ordered_items.is_empty()
-    } {
+    if !crawler.stopped
+        && collection.collection_items.total_items != Some(0)
+        && collection.collection_items.items.is_empty()
+        && collection.ordered_collection_items.ordered_items.is_empty()
+    {
         match &collection.collection_items.first {
             None => {
-                // do nothing
+                crawler.state.resume_page_url = None;
             }
             Some(init_collection_page_ref) => {
                 fetch_outbox_collection_pages(
@@ -571,14 +1243,72 @@ async fn fetch_outbox_collection<'a>(
                         .unwrap_or(env.default_max_pages),
                     account,
                     init_collection_page_ref,
-                    &mut new_outbox_collection_manager,
+                    &mut crawler,
                 )
                 .await?;
             }
         }
     }
 
-    let new_outbox_collection = new_outbox_collection_manager.save_rest_items(env).await?;
+    if let Some(prev_state) = prev_state_opt {
+        if !crawler.stopped && !prev_state.archived_items.is_empty() {
+            println!(
+                "Warning: No archived activities are found in new activities, so some activities may be missing: account={}",
+                account.ident
+            );
+        }
+
+        let new_activities_count = crawler.state.archived_items.len();
+        let mut failed_items = prev_state.failed_items.into_iter().peekable();
+        // New activities never contain archived ones, since crawling is stopped at them.
+        for (position, item) in prev_state.archived_items.into_iter().enumerate() {
+            while let Some(failed_item) = failed_items.next_if(|x| x.position <= position) {
+                retry_failed_activity(env, account, failed_item, &mut crawler).await?;
+            }
+
+            let activity_path = format!("{}activity.json", item.base_path);
+            let activity: ap_model::Object =
+                match env.output.load_static_json_resource(&activity_path).await? {
+                    None => {
+                        println!("Warning: An archived activity is missing: path={activity_path}");
+                        continue;
+                    }
+                    Some(x) => x,
+                };
+            crawler.add_activity(env, item, activity).await?;
+        }
+        for failed_item in failed_items {
+            retry_failed_activity(env, account, failed_item, &mut crawler).await?;
+        }
+        println!(
+            "Info: Fetched new activities: account={}, count={new_activities_count}",
+            account.ident
+        );
+
+        crawler.stopped = false;
+        crawler.on_known_activity = OnKnownActivity::Skip;
+        crawler.save_progress = true;
+        crawler
+            .set_resume_page(env, account, prev_state.resume_page_url.clone())
+            .await?;
+        if let Some(resume_page_url) = prev_state.resume_page_url {
+            println!(
+                "Info: Resume fetching outbox: account={}, page={resume_page_url}",
+                account.ident
+            );
+            fetch_outbox_collection_pages(
+                env,
+                env.default_max_pages,
+                account,
+                &ap_model::ObjectOrLink::Link(ap_model::Link::from(resume_page_url)),
+                &mut crawler,
+            )
+            .await?;
+        }
+    }
+
+    let state = crawler.state;
+    let new_outbox_collection = crawler.manager.save_rest_items(env).await?;
     let new_outbox_url = save_outbox_collection(
         env,
         &format!("{}outbox.json", account.base_path),
@@ -588,7 +1318,12 @@ async fn fetch_outbox_collection<'a>(
     )
     .await?;
 
-    Ok(new_outbox_url)
+    env.output.save_crawl_state(&account.ident, &state).await?;
+
+    Ok(NewOutbox {
+        url: new_outbox_url,
+        total_items_count: new_outbox_collection.total_items_count,
+    })
 }
 
 async fn fetch_outbox_collection_pages<'a>(
@@ -596,14 +1331,14 @@ async fn fetch_outbox_collection_pages<'a>(
     max_pages_count: usize,
     account: &Account,
     init_collection_page_ref: &ap_model::ObjectOrLink,
-    new_outbox_collection_manager: &mut NewOutboxCollectionManager,
+    crawler: &mut OutboxCrawler,
 ) -> Result<(), Box<dyn Error>> {
     let result = fetch_outbox_collection_pages_in_object_and_fetch_next(
         env,
         max_pages_count,
         account,
         init_collection_page_ref,
-        new_outbox_collection_manager,
+        crawler,
     )
     .await?;
 
@@ -615,7 +1350,7 @@ async fn fetch_outbox_collection_pages<'a>(
             max_pages_count,
             account,
             &ap_model::ObjectOrLink::Object(next_page),
-            new_outbox_collection_manager,
+            crawler,
         )
         .await?;
 
@@ -630,16 +1365,21 @@ struct FetchNextCollectionPageResult {
     next_page_opt: Option<ap_model::Object>,
     fetched_pages_count: usize,
 }
+
 async fn fetch_outbox_collection_pages_in_object_and_fetch_next<'a>(
     env: &Env<'a>,
     max_pages_count: usize,
     account: &Account,
     collection_page_ref: &ap_model::ObjectOrLink,
-    new_outbox_collection_manager: &mut NewOutboxCollectionManager,
+    crawler: &mut OutboxCrawler,
 ) -> Result<FetchNextCollectionPageResult, Box<dyn Error>> {
     let mut collection_page_ref = collection_page_ref;
     let mut fetched_pages_count: usize = 0;
     loop {
+        crawler
+            .set_resume_page(env, account, object_or_link_id(collection_page_ref))
+            .await?;
+
         if fetched_pages_count >= max_pages_count {
             return Ok(FetchNextCollectionPageResult {
                 next_page_opt: None,
@@ -650,25 +1390,37 @@ async fn fetch_outbox_collection_pages_in_object_and_fetch_next<'a>(
         match collection_page_ref {
             ap_model::ObjectOrLink::Link(collection_page_ref) => {
                 let next_page_uri = collection_page_ref.href.to_string();
-                let next_page = activitypub::fetch_object(&env.client, next_page_uri).await?;
+                let next_page = activitypub::fetch_object(&env.client, next_page_uri)
+                    .await?
+                    .data;
                 return Ok(FetchNextCollectionPageResult {
                     next_page_opt: Some(next_page),
                     fetched_pages_count: fetched_pages_count + 1,
                 });
             }
             ap_model::ObjectOrLink::Object(collection_page) => {
-                for item in &collection_page.collection_items.items {
-                    fetch_outbox_activity_ref(env, account, item, new_outbox_collection_manager)
-                        .await?;
-                }
+                fetch_outbox_activity_refs(
+                    env,
+                    account,
+                    collection_page
+                        .collection_items
+                        .items
+                        .iter()
+                        .chain(&collection_page.ordered_collection_items.ordered_items),
+                    crawler,
+                )
+                .await?;
 
-                for item in &collection_page.ordered_collection_items.ordered_items {
-                    fetch_outbox_activity_ref(env, account, item, new_outbox_collection_manager)
-                        .await?;
+                if crawler.stopped {
+                    return Ok(FetchNextCollectionPageResult {
+                        next_page_opt: None,
+                        fetched_pages_count,
+                    });
                 }
 
                 match &collection_page.collection_page_items.next {
                     None => {
+                        crawler.set_resume_page(env, account, None).await?;
                         return Ok(FetchNextCollectionPageResult {
                             next_page_opt: None,
                             fetched_pages_count,
@@ -684,20 +1436,119 @@ async fn fetch_outbox_collection_pages_in_object_and_fetch_next<'a>(
     }
 }
 
+/**
+ * Fetch activities concurrently, and add them into the collection in the original order.
+ */
+async fn fetch_outbox_activity_refs<'a, 'b>(
+    env: &Env<'a>,
+    account: &Account,
+    activity_refs: impl Iterator<Item = &'b ap_model::ObjectOrLink>,
+    crawler: &mut OutboxCrawler,
+) -> Result<(), Box<dyn Error>> {
+    let mut new_activity_refs = vec![];
+    for activity_ref in activity_refs {
+        if crawler.is_known(activity_ref) {
+            match crawler.on_known_activity {
+                OnKnownActivity::Stop => {
+                    crawler.stopped = true;
+                    break;
+                }
+                OnKnownActivity::Skip => continue,
+            }
+        }
+        new_activity_refs.push(activity_ref);
+    }
+
+    let mut new_activities_results = stream::iter(new_activity_refs)
+        .map(|activity_ref| async move {
+            (
+                object_or_link_id(activity_ref),
+                activity_content_hash(activity_ref),
+                fetch_outbox_activity_ref(env, account, activity_ref).await,
+            )
+        })
+        .buffered(env.concurrency);
+    while let Some((activity_id, content_hash, result)) = new_activities_results.next().await {
+        let new_activities = match result {
+            Ok(x) => x,
+            Err(err) if env.continue_on_error => {
+                println!(
+                    "Warning: Failed to archive an activity: id={}, err={err}",
+                    activity_id.as_deref().unwrap_or("(none)")
+                );
+                if let Some(activity_id) = &activity_id {
+                    crawler.add_failure(activity_id.clone());
+                }
+                env.reporter
+                    .record_item_failure(&account.ident, activity_id, err.as_ref());
+                continue;
+            }
+            Err(err) => {
+                env.reporter
+                    .record_item_failure(&account.ident, activity_id, err.as_ref());
+                return Err(err);
+            }
+        };
+
+        if new_activities.is_empty() {
+            env.reporter.record_skipped(&account.ident);
+        }
+        for new_activity in new_activities {
+            env.reporter.record_archived(&account.ident);
+            crawler
+                .add_activity(
+                    env,
+                    ArchivedItem {
+                        activity_id: activity_id.clone(),
+                        content_hash: content_hash.clone(),
+                        base_path: new_activity.base_path,
+                    },
+                    new_activity.activity,
+                )
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Fetch the activity failed in the previous run again, at its position in the outbox.
+ */
+async fn retry_failed_activity<'a>(
+    env: &Env<'a>,
+    account: &Account,
+    failed_item: FailedItem,
+    crawler: &mut OutboxCrawler,
+) -> Result<(), Box<dyn Error>> {
+    println!(
+        "Info: Retry a failed activity: account={}, id={}",
+        account.ident, failed_item.activity_id
+    );
+    crawler.known_activity_keys.remove(&failed_item.activity_id);
+    let activity_ref =
+        ap_model::ObjectOrLink::Link(ap_model::Link::from(failed_item.activity_id.as_str()));
+    fetch_outbox_activity_refs(env, account, [&activity_ref].into_iter(), crawler).await
+}
+
+struct NewActivity {
+    base_path: String,
+    activity: ap_model::Object,
+}
+
 async fn fetch_outbox_activity_ref<'a>(
     env: &Env<'a>,
     account: &Account,
     activity_ref: &ap_model::ObjectOrLink,
-    new_outbox_collection_manager: &mut NewOutboxCollectionManager,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<Vec<NewActivity>, Box<dyn Error>> {
     match activity_ref {
         ap_model::ObjectOrLink::Link(activity_ref) => {
             let uri = activity_ref.href.to_string();
-            let activity = activitypub::fetch_object(&env.client, uri).await?;
-            fetch_outbox_activity(env, account, &activity, new_outbox_collection_manager).await
+            let activity = activitypub::fetch_object(&env.client, uri).await?.data;
+            fetch_outbox_activity(env, account, &activity).await
         }
         ap_model::ObjectOrLink::Object(activity) => {
-            fetch_outbox_activity(env, account, activity, new_outbox_collection_manager).await
+            fetch_outbox_activity(env, account, activity).await
         }
     }
 }
@@ -706,8 +1557,7 @@ async fn fetch_outbox_activity<'a>(
     env: &Env<'a>,
     account: &Account,
     activity: &ap_model::Object,
-    new_outbox_collection_manager: &mut NewOutboxCollectionManager,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<Vec<NewActivity>, Box<dyn Error>> {
     {
         let mut accepted_type = false;
         for typ in &activity.typ {
@@ -722,10 +1572,11 @@ async fn fetch_outbox_activity<'a>(
             }
         }
         if !accepted_type {
-            return Ok(());
+            return Ok(vec![]);
         }
     }
 
+    let mut new_activities = vec![];
     for object_ref in &activity.activity_items.object {
         let new_object = fetch_outbox_object_ref(env, account, object_ref).await?;
 
@@ -739,12 +1590,13 @@ async fn fetch_outbox_activity<'a>(
         )
         .await?;
 
-        new_outbox_collection_manager
-            .add_activity_and_save_if_needed(env, new_object.base_path, new_activity)
-            .await?;
+        new_activities.push(NewActivity {
+            base_path: new_object.base_path,
+            activity: new_activity,
+        });
     }
 
-    Ok(())
+    Ok(new_activities)
 }
 
 async fn fetch_outbox_object_ref<'a>(
@@ -755,8 +1607,11 @@ async fn fetch_outbox_object_ref<'a>(
     match object_ref {
         ap_model::ObjectOrLink::Link(object_ref) => {
             let uri = object_ref.href.to_string();
-            let object = activitypub::fetch_object(&env.client, uri).await?;
-            save_outbox_object(env, account, &object).await
+            let fetched_object = activitypub::fetch_object(&env.client, uri).await?;
+            let new_object = save_outbox_object(env, account, &fetched_object.data).await?;
+            save_redirected_from_maps(env, &fetched_object.redirected_from, &new_object.url)
+                .await?;
+            Ok(new_object)
         }
         ap_model::ObjectOrLink::Object(object) => save_outbox_object(env, account, object).await,
     }
@@ -764,28 +1619,23 @@ async fn fetch_outbox_object_ref<'a>(
 
 struct NewObject {
     base_path: String,
+    url: Url,
     object: ap_model::Object,
 }
 
-static RE_ID: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r".*/(statuses|notes)/(?<id>[a-z0-9]+)$").unwrap());
-
 async fn save_outbox_object<'a>(
     env: &Env<'a>,
     account: &Account,
     object: &ap_model::Object,
 ) -> Result<NewObject, Box<dyn Error>> {
-    let Some(caps) = (match &object.id {
-        None => return Err(format!("Object ID should be available.").into()),
-        Some(x) => RE_ID.captures(x),
-    }) else {
-        return Err(format!(
-            "The format of object ID is not supported: id={:?}",
-            &object.id
-        )
-        .into());
+    let id = match &object.id {
+        None => return Err("Object ID should be available.".into()),
+        Some(x) => env.object_path_resolver.resolve(x)?,
     };
-    let id = &caps["id"];
+
+    let mut mirrored_object = object.clone();
+    mirror_object_media(env, &mut mirrored_object).await?;
+    let object = &mirrored_object;
 
     let save_json_path = format!("{}entities/{id}.json", account.base_path);
     let new_object_url = env.static_base_url.join(&save_json_path)?;
@@ -819,8 +1669,8 @@ async fn save_outbox_object<'a>(
             name_map: object.object_items.name_map.clone(),
             duration: object.object_items.duration.clone(),
             media_type: object.object_items.media_type.clone(),
-            end_time: object.object_items.end_time.clone(),
-            published: object.object_items.published.clone(),
+            end_time: object.object_items.end_time,
+            published: object.object_items.published,
             summary: object.object_items.summary.clone(),
             summary_map: object.object_items.summary_map.clone(),
             describes: object.object_items.describes.clone(),
@@ -862,14 +1712,17 @@ async fn save_outbox_object<'a>(
                 },
                 content: object.object_items.content.first().cloned(),
                 content_map: object.object_items.content_map.clone(),
+                attachments: object
+                    .object_items
+                    .attachment
+                    .iter()
+                    .filter_map(media_url)
+                    .collect(),
                 url: match &object.object_items.url {
                     Some(item) => Some(item.href.to_string()),
                     None => object.id.clone(),
                 },
-                published: match object.object_items.published {
-                    None => None,
-                    Some(item) => Some(item.to_rfc3339()),
-                },
+                published: object.object_items.published.map(|item| item.to_rfc3339()),
             })?,
         )
         .await?;
@@ -907,6 +1760,7 @@ async fn save_outbox_object<'a>(
 
     Ok(NewObject {
         base_path: format!("{}entities/{id}/", account.base_path),
+        url: new_object_url,
         object: new_object,
     })
 }
@@ -948,8 +1802,8 @@ async fn save_outbox_activity<'a>(
             name_map: original_activity.object_items.name_map.clone(),
             duration: original_activity.object_items.duration.clone(),
             media_type: original_activity.object_items.media_type.clone(),
-            end_time: original_activity.object_items.end_time.clone(),
-            published: original_activity.object_items.published.clone(),
+            end_time: original_activity.object_items.end_time,
+            published: original_activity.object_items.published,
             summary: original_activity.object_items.summary.clone(),
             summary_map: original_activity.object_items.summary_map.clone(),
             describes: original_activity.object_items.describes.clone(),
@@ -1000,11 +1854,11 @@ async fn save_outbox_collection_page<'a>(
     prev_page_url_opt: &Option<Url>,
     items: Vec<ap_model::ObjectOrLink>,
 ) -> Result<(), Box<dyn Error>> {
-    let page_url = env.static_base_url.join(&save_page_path)?;
+    let page_url = env.static_base_url.join(save_page_path)?;
 
     env.output
         .save_static_json_resource(
-            &save_page_path,
+            save_page_path,
             &ap_model::Object {
                 schema_context: Some(ap_model::Context::object_default()),
                 id: Some(page_url.to_string()),
@@ -1015,12 +1869,11 @@ async fn save_outbox_collection_page<'a>(
                 collection_items: ap_model::CollectionItems {
                     total_items: None,
                     current: None,
-                    first: match &first_page_url_opt {
-                        None => None,
-                        Some(first_page_url) => Some(Box::new(ap_model::ObjectOrLink::Link(
-                            ap_model::Link::from(first_page_url.as_str()),
-                        ))),
-                    },
+                    first: first_page_url_opt.as_ref().map(|first_page_url| {
+                        Box::new(ap_model::ObjectOrLink::Link(ap_model::Link::from(
+                            first_page_url.as_str(),
+                        )))
+                    }),
                     last: None,
                     items: vec![],
                 },
@@ -1029,12 +1882,11 @@ async fn save_outbox_collection_page<'a>(
                 },
                 collection_page_items: ap_model::CollectionPageItems {
                     next: None,
-                    prev: match &prev_page_url_opt {
-                        None => None,
-                        Some(prev_page_url) => Some(Box::new(ap_model::ObjectOrLink::Link(
-                            ap_model::Link::from(prev_page_url.as_str()),
-                        ))),
-                    },
+                    prev: prev_page_url_opt.as_ref().map(|prev_page_url| {
+                        Box::new(ap_model::ObjectOrLink::Link(ap_model::Link::from(
+                            prev_page_url.as_str(),
+                        )))
+                    }),
                     part_of: None,
                 },
                 ordered_collection_page_items: ap_model::OrderedCollectionPageItems {
@@ -1072,18 +1924,16 @@ async fn save_outbox_collection<'a>(
                 vec!["OrderedCollection".to_string()],
                 Some(total_items_count),
                 None,
-                match first_page_url_opt {
-                    None => None,
-                    Some(first_page_url) => Some(Box::new(ap_model::ObjectOrLink::Link(
-                        ap_model::Link::from(first_page_url.to_string()),
-                    ))),
-                },
-                match last_page_url_opt {
-                    None => None,
-                    Some(last_page_url) => Some(Box::new(ap_model::ObjectOrLink::Link(
-                        ap_model::Link::from(last_page_url.to_string()),
-                    ))),
-                },
+                first_page_url_opt.map(|first_page_url| {
+                    Box::new(ap_model::ObjectOrLink::Link(ap_model::Link::from(
+                        first_page_url.to_string(),
+                    )))
+                }),
+                last_page_url_opt.map(|last_page_url| {
+                    Box::new(ap_model::ObjectOrLink::Link(ap_model::Link::from(
+                        last_page_url.to_string(),
+                    )))
+                }),
                 vec![],
                 vec![],
             ),
@@ -1100,56 +1950,256 @@ async fn save_redirect_map<'a>(
     media_type: &[String],
     new_url: &Url,
 ) -> Result<(), Box<dyn Error>> {
-    match env
-        .output
-        .get_redirect_map_resource(domain, url_path)
-        .await?
-    {
-        None => {
-            let redirect_map = RedirectMap::new();
-            save_redirect_map_with_old_resource(
-                env,
-                domain,
-                url_path,
-                redirect_map,
-                media_type,
-                new_url,
-            )
-            .await
+    env.output
+        .update_redirect_map_resource(domain, url_path, |redirect_map| {
+            if media_type.is_empty() {
+                redirect_map.insert_entry("*/*".to_string(), new_url);
+            }
+
+            for typ in media_type {
+                redirect_map.insert_entry(typ.to_string(), new_url);
+            }
+        })
+        .await
+}
+
+async fn save_redirected_from_maps<'a>(
+    env: &Env<'a>,
+    redirected_from: &[Url],
+    new_url: &Url,
+) -> Result<(), Box<dyn Error>> {
+    for old_url in redirected_from {
+        match FullUrl::parse(old_url.as_str()) {
+            Ok(old_url) => {
+                save_redirect_map(
+                    env,
+                    old_url.domain(),
+                    old_url.path(),
+                    &["application/activity+json".to_string()],
+                    new_url,
+                )
+                .await?;
+            }
+            Err(err) => {
+                println!("Warning: Redirected URL is illegal: url={old_url}, err={err}");
+            }
         }
-        Some(redirect_map) => {
-            save_redirect_map_with_old_resource(
-                env,
-                domain,
-                url_path,
-                redirect_map,
-                media_type,
-                new_url,
-            )
-            .await
+    }
+
+    Ok(())
+}
+
+fn media_url(media_ref: &ap_model::ObjectOrLink) -> Option<String> {
+    match media_ref {
+        ap_model::ObjectOrLink::Link(link) => Some(link.href.to_string()),
+        ap_model::ObjectOrLink::Object(object) => {
+            object.object_items.url.as_ref().map(|x| x.href.to_string())
         }
     }
 }
 
-async fn save_redirect_map_with_old_resource<'a>(
+fn first_media_url(media_refs: &[ap_model::ObjectOrLink]) -> Option<String> {
+    media_refs.iter().find_map(media_url)
+}
+
+async fn mirror_object_media<'a>(
     env: &Env<'a>,
-    domain: &str,
-    url_path: &str,
-    mut redirect_map: RedirectMap,
-    media_type: &[String],
-    new_url: &Url,
+    object: &mut ap_model::Object,
 ) -> Result<(), Box<dyn Error>> {
-    if media_type.is_empty() {
-        redirect_map.insert_entry("*/*".to_string(), new_url);
+    if !env.fetch_media {
+        return Ok(());
+    }
+
+    let mut mirrored_urls = HashMap::new();
+
+    let object_items = &mut object.object_items;
+    for media_ref in object_items
+        .attachment
+        .iter_mut()
+        .chain(object_items.icon.iter_mut())
+        .chain(object_items.image.iter_mut())
+    {
+        mirror_media_ref(env, media_ref, &mut mirrored_urls).await?;
     }
 
-    for typ in media_type {
-        redirect_map.insert_entry(typ.to_string(), new_url);
+    // Custom emojis are referred from contents via their icons.
+    for tag in &mut object_items.tag {
+        if let ap_model::ObjectOrLink::Object(tag) = tag {
+            if tag.typ.iter().any(|x| x == "Emoji") {
+                for media_ref in &mut tag.object_items.icon {
+                    mirror_media_ref(env, media_ref, &mut mirrored_urls).await?;
+                }
+            }
+        }
     }
 
-    env.output
-        .save_redirect_map_resource(domain, url_path, &redirect_map)
-        .await?;
+    for text in object_items
+        .content
+        .iter_mut()
+        .chain(object_items.content_map.values_mut())
+        .chain(object_items.summary.iter_mut())
+        .chain(object_items.summary_map.values_mut())
+    {
+        for (old_url, new_url) in &mirrored_urls {
+            *text = text.replace(old_url, new_url.as_str());
+        }
+    }
 
     Ok(())
 }
+
+async fn mirror_media_ref<'a>(
+    env: &Env<'a>,
+    media_ref: &mut ap_model::ObjectOrLink,
+    mirrored_urls: &mut HashMap<String, Url>,
+) -> Result<(), Box<dyn Error>> {
+    let Some(link) = media_link_mut(media_ref) else {
+        return Ok(());
+    };
+
+    if let Some(new_url) = mirror_media_url(env, &link.href).await? {
+        mirrored_urls.insert(link.href.to_string(), new_url.clone());
+        link.href = new_url.to_string();
+    }
+
+    Ok(())
+}
+
+fn media_link_mut(media_ref: &mut ap_model::ObjectOrLink) -> Option<&mut ap_model::Link> {
+    match media_ref {
+        ap_model::ObjectOrLink::Link(link) => Some(link),
+        ap_model::ObjectOrLink::Object(object) => object.object_items.url.as_mut(),
+    }
+}
+
+static RE_MEDIA_EXT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\.(?<ext>[a-zA-Z0-9]{1,8})$").unwrap());
+
+fn media_ext(path: &str, content_type: Option<&str>) -> Option<String> {
+    match RE_MEDIA_EXT.captures(path) {
+        Some(caps) => Some(caps["ext"].to_lowercase()),
+        None => content_type
+            .and_then(|x| x.split(';').next())
+            .and_then(|x| mime_guess::get_mime_extensions_str(x.trim()))
+            .and_then(|x| x.first())
+            .map(|x| x.to_string()),
+    }
+}
+
+fn media_hash(original: &str) -> String {
+    format!("{:x}", Sha256::digest(original.as_bytes()))
+}
+
+fn media_save_path(domain: &str, original: &str, ext: Option<&str>) -> String {
+    let hash = media_hash(original);
+    match ext {
+        None => format!("media/{domain}/{hash}"),
+        Some(ext) => format!("media/{domain}/{hash}.{ext}"),
+    }
+}
+
+/**
+ * Find the media saved by earlier runs.
+ *
+ * Media without extensions in their URLs are named by their content types,
+ * so they are found by their redirect maps instead.
+ */
+async fn find_saved_media<'a>(
+    env: &Env<'a>,
+    old_url: &FullUrl,
+    original_url: &str,
+) -> Result<Option<String>, Box<dyn Error>> {
+    if let Some(ext) = media_ext(old_url.path(), None) {
+        let save_path = media_save_path(old_url.domain(), original_url, Some(&ext));
+        if env.output.exists_static_resource(&save_path).await? {
+            return Ok(Some(save_path));
+        }
+        return Ok(None);
+    }
+
+    let redirect_map = match env
+        .output
+        .get_redirect_map_resource(old_url.domain(), old_url.path())
+        .await?
+    {
+        None => return Ok(None),
+        Some(x) => x,
+    };
+    let save_path = match redirect_map.get_entry("*/*").and_then(|new_url| {
+        new_url
+            .as_str()
+            .strip_prefix(env.static_base_url.as_str())
+            .map(|x| x.to_string())
+    }) {
+        None => return Ok(None),
+        Some(x) => x,
+    };
+
+    // Query strings are not in redirect maps, so the name is checked to be of this URL.
+    let save_path_base = media_save_path(old_url.domain(), original_url, None);
+    let is_named = save_path == save_path_base
+        || save_path
+            .strip_prefix(&save_path_base)
+            .is_some_and(|ext| ext.starts_with('.'));
+    if is_named && env.output.exists_static_resource(&save_path).await? {
+        Ok(Some(save_path))
+    } else {
+        Ok(None)
+    }
+}
+
+async fn mirror_media_url<'a>(
+    env: &Env<'a>,
+    original_url: &str,
+) -> Result<Option<Url>, Box<dyn Error>> {
+    let mirrored = env
+        .mirrored_media
+        .entry(original_url)
+        .get_or_try_init(|| mirror_media_url_uncached(env, original_url))
+        .await?
+        .clone();
+    Ok(mirrored)
+}
+
+async fn mirror_media_url_uncached<'a>(
+    env: &Env<'a>,
+    original_url: &str,
+) -> Result<Option<Url>, Box<dyn Error>> {
+    let old_url = match FullUrl::parse(original_url) {
+        Ok(x) => x,
+        Err(err) => {
+            println!("Warning: URL of media is illegal: url={original_url}, err={err}");
+            return Ok(None);
+        }
+    };
+
+    let save_path = match find_saved_media(env, &old_url, original_url).await? {
+        Some(save_path) => save_path,
+        None => {
+            let media = match media::fetch_media(&env.client, original_url).await {
+                Ok(x) => x,
+                Err(err) => {
+                    println!("Warning: Failed to fetch media: url={original_url}, err={err}");
+                    return Ok(None);
+                }
+            };
+
+            let save_path = media_save_path(
+                old_url.domain(),
+                original_url,
+                media_ext(old_url.path(), media.content_type.as_deref()).as_deref(),
+            );
+            env.output
+                .save_static_bytes_resource(&save_path, &media.bytes)
+                .await?;
+
+            save_path
+        }
+    };
+
+    let new_url = env.static_base_url.join(&save_path)?;
+
+    save_redirect_map(env, old_url.domain(), old_url.path(), &[], &new_url).await?;
+
+    Ok(Some(new_url))
+}
@@ -0,0 +1,131 @@
+use std::error::Error;
+
+use clap::ValueEnum;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+
+/**
+ * A rule to map IDs of objects into names of archived entities.
+ *
+ * Names should be valid as path components of URLs.
+ */
+pub trait ObjectPathRule {
+    fn resolve(&self, id: &str) -> Option<String>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ObjectPathRuleKind {
+    /// Mastodon `/statuses/<id>` and Misskey `/notes/<id>`.
+    StatusesOrNotes,
+    /// GoToSocial `/statuses/<ULID>`.
+    Gotosocial,
+    /// Pleroma and Akkoma `/objects/<UUID>`.
+    Pleroma,
+    /// Lemmy `/post/<id>` and `/comment/<id>`.
+    Lemmy,
+    /// PeerTube `/videos/watch/<UUID>`.
+    Peertube,
+    /// SHA-256 hash of any IDs.
+    Hash,
+}
+
+impl ObjectPathRuleKind {
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::StatusesOrNotes,
+            Self::Gotosocial,
+            Self::Pleroma,
+            Self::Lemmy,
+            Self::Peertube,
+            Self::Hash,
+        ]
+    }
+}
+
+static RE_STATUSES_OR_NOTES: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r".*/(statuses|notes)/(?<id>[a-z0-9]+)$").unwrap());
+static RE_GOTOSOCIAL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r".*/statuses/(?<id>[0-9A-Z]{26})$").unwrap());
+static RE_PLEROMA: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r".*/objects/(?<id>[0-9a-f]{8}(-[0-9a-f]{4}){3}-[0-9a-f]{12})$").unwrap()
+});
+static RE_LEMMY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r".*/(?<kind>post|comment)/(?<id>[0-9]+)$").unwrap());
+static RE_PEERTUBE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r".*/videos/watch/(?<id>[0-9a-f]{8}(-[0-9a-f]{4}){3}-[0-9a-f]{12})$").unwrap()
+});
+
+struct RegexRule {
+    regex: &'static Lazy<Regex>,
+    prefix_kind: bool,
+}
+
+impl ObjectPathRule for RegexRule {
+    fn resolve(&self, id: &str) -> Option<String> {
+        let caps = self.regex.captures(id)?;
+        if self.prefix_kind {
+            Some(format!("{}-{}", &caps["kind"], &caps["id"]))
+        } else {
+            Some(caps["id"].to_string())
+        }
+    }
+}
+
+struct HashRule;
+
+impl ObjectPathRule for HashRule {
+    fn resolve(&self, id: &str) -> Option<String> {
+        Some(format!("{:x}", Sha256::digest(id.as_bytes())))
+    }
+}
+
+pub struct ObjectPathResolver {
+    rules: Vec<Box<dyn ObjectPathRule>>,
+}
+
+impl ObjectPathResolver {
+    pub fn new(rules: Vec<Box<dyn ObjectPathRule>>) -> Self {
+        Self { rules }
+    }
+
+    pub fn from_kinds(kinds: &[ObjectPathRuleKind]) -> Self {
+        Self::new(
+            kinds
+                .iter()
+                .map(|kind| -> Box<dyn ObjectPathRule> {
+                    match kind {
+                        ObjectPathRuleKind::StatusesOrNotes => Box::new(RegexRule {
+                            regex: &RE_STATUSES_OR_NOTES,
+                            prefix_kind: false,
+                        }),
+                        ObjectPathRuleKind::Gotosocial => Box::new(RegexRule {
+                            regex: &RE_GOTOSOCIAL,
+                            prefix_kind: false,
+                        }),
+                        ObjectPathRuleKind::Pleroma => Box::new(RegexRule {
+                            regex: &RE_PLEROMA,
+                            prefix_kind: false,
+                        }),
+                        ObjectPathRuleKind::Lemmy => Box::new(RegexRule {
+                            regex: &RE_LEMMY,
+                            prefix_kind: true,
+                        }),
+                        ObjectPathRuleKind::Peertube => Box::new(RegexRule {
+                            regex: &RE_PEERTUBE,
+                            prefix_kind: false,
+                        }),
+                        ObjectPathRuleKind::Hash => Box::new(HashRule),
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    pub fn resolve(&self, id: &str) -> Result<String, Box<dyn Error>> {
+        match self.rules.iter().find_map(|rule| rule.resolve(id)) {
+            None => Err(format!("The format of object ID is not supported: id={id}").into()),
+            Some(x) => Ok(x),
+        }
+    }
+}
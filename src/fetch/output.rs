@@ -1,35 +1,135 @@
+use crate::compression::{self, Encoding, Level};
+use crate::manifest::Manifest;
+use crate::redirect_map::RedirectMap;
+use crate::resource_path::ResourcePath;
+use crate::webfinger;
+use crate::webfinger::alias::Alias as WebfingerAlias;
+use crate::webfinger::resource::Resource as WebfingerResource;
 use activitist::json::JsonSerde;
-use archivedon::redirect_map::RedirectMap;
-use archivedon::resource_path::ResourcePath;
-use archivedon::webfinger::resource::Resource as WebfingerResource;
-use std::{error::Error, path::Path};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 use tokio::fs;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
 
 pub struct Output {
     resource_path: ResourcePath,
+    /// Write `.br` and `.gz` siblings of files served statically.
+    precompress: bool,
+    path_locks: PathLocks,
+}
+
+/**
+ * Locks per file path, since the same files such as redirect maps and media are written by concurrent tasks.
+ */
+#[derive(Default)]
+struct PathLocks {
+    locks: Mutex<HashMap<PathBuf, Arc<AsyncMutex<()>>>>,
+}
+
+struct PathLockGuard<'a> {
+    locks: &'a PathLocks,
+    path: PathBuf,
+    lock: Arc<AsyncMutex<()>>,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl PathLocks {
+    async fn lock(&self, path: &Path) -> PathLockGuard<'_> {
+        let lock = self.inner().entry(path.to_path_buf()).or_default().clone();
+        let guard = lock.clone().lock_owned().await;
+        PathLockGuard {
+            locks: self,
+            path: path.to_path_buf(),
+            lock,
+            guard: Some(guard),
+        }
+    }
+
+    fn inner(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, Arc<AsyncMutex<()>>>> {
+        match self.locks.lock() {
+            Ok(locks) => locks,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl Drop for PathLockGuard<'_> {
+    fn drop(&mut self) {
+        let mut locks = self.locks.inner();
+        self.guard = None;
+        // Only the map and this guard refer to the lock if no other tasks wait for it.
+        if Arc::strong_count(&self.lock) == 2 {
+            locks.remove(&self.path);
+        }
+    }
 }
 
 impl Output {
-    pub async fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+    pub async fn load(path: &Path, precompress: bool) -> Result<Self, Box<dyn Error>> {
         fs::create_dir_all(&path).await?;
         Ok(Self {
             resource_path: ResourcePath::new(fs::canonicalize(path).await?),
+            precompress,
+            path_locks: PathLocks::default(),
         })
     }
 
     pub async fn save_top_page(&self, content: &str) -> Result<(), Box<dyn Error>> {
         let save_path = &self.resource_path.index_html_path;
-        fs::write(save_path, content).await?;
+        self.write_served_file(save_path, content.as_bytes().to_vec())
+            .await
+    }
+
+    pub async fn load_manifest(&self) -> Result<Option<Manifest>, Box<dyn Error>> {
+        let save_path = &self.resource_path.manifest_path;
+        if fs::try_exists(save_path).await? {
+            Ok(Some(serde_json::from_slice(&fs::read(save_path).await?)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn save_manifest(&self, manifest: &Manifest) -> Result<(), Box<dyn Error>> {
+        let save_path = &self.resource_path.manifest_path;
+        fs::write(save_path, serde_json::to_vec(manifest)?).await?;
         Ok(())
     }
 
+    /**
+     * Save the resource, and index it by its aliases and given other aliases.
+     */
     pub async fn save_webfinger_resource(
         &self,
         content: &WebfingerResource,
+        other_aliases: &[String],
     ) -> Result<(), Box<dyn Error>> {
-        let save_path = self.resource_path.webfinger_path(&content.subject);
+        let save_path = self
+            .resource_path
+            .webfinger_path(&webfinger::acct::normalize_resource(&content.subject));
         fs::create_dir_all(save_path.parent().unwrap()).await?;
         fs::write(&save_path, serde_json::to_vec(content)?).await?;
+
+        let alias_content = serde_json::to_vec(&WebfingerAlias {
+            subject: content.subject.clone(),
+        })?;
+        let aliases = content.aliases.iter().flatten().chain(other_aliases);
+        for alias in aliases {
+            if alias == &content.subject {
+                continue;
+            }
+            let alias_path = self
+                .resource_path
+                .webfinger_alias_path(&webfinger::acct::normalize_resource(alias));
+            fs::create_dir_all(alias_path.parent().unwrap()).await?;
+            fs::write(&alias_path, &alias_content).await?;
+        }
+
         Ok(())
     }
 
@@ -40,8 +140,8 @@ impl Output {
     ) -> Result<(), Box<dyn Error>> {
         let save_path = self.resource_path.static_root_dir.join(path);
         fs::create_dir_all(save_path.parent().unwrap()).await?;
-        fs::write(&save_path, content.to_json_bytes()?).await?;
-        Ok(())
+        self.write_served_file(&save_path, content.to_json_bytes()?)
+            .await
     }
 
     pub async fn save_static_text_resource(
@@ -51,8 +151,35 @@ impl Output {
     ) -> Result<(), Box<dyn Error>> {
         let save_path = self.resource_path.static_root_dir.join(path);
         fs::create_dir_all(save_path.parent().unwrap()).await?;
-        fs::write(&save_path, content).await?;
-        Ok(())
+        self.write_served_file(&save_path, content.as_bytes().to_vec())
+            .await
+    }
+
+    pub async fn save_static_bytes_resource(
+        &self,
+        path: &str,
+        content: &[u8],
+    ) -> Result<(), Box<dyn Error>> {
+        let save_path = self.resource_path.static_root_dir.join(path);
+        fs::create_dir_all(save_path.parent().unwrap()).await?;
+        self.write_served_file(&save_path, content.to_vec()).await
+    }
+
+    pub async fn load_static_json_resource<T: JsonSerde>(
+        &self,
+        path: &str,
+    ) -> Result<Option<T>, Box<dyn Error>> {
+        let save_path = self.resource_path.static_root_dir.join(path);
+        if fs::try_exists(&save_path).await? {
+            Ok(Some(T::from_json_bytes(&fs::read(&save_path).await?)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn exists_static_resource(&self, path: &str) -> Result<bool, Box<dyn Error>> {
+        let save_path = self.resource_path.static_root_dir.join(path);
+        Ok(fs::try_exists(&save_path).await?)
     }
 
     pub async fn get_redirect_map_resource(
@@ -61,6 +188,10 @@ impl Output {
         url_path: &str,
     ) -> Result<Option<RedirectMap>, Box<dyn Error>> {
         let save_path = self.resource_path.redirect_map_path(domain, url_path);
+        Self::read_redirect_map(&save_path).await
+    }
+
+    async fn read_redirect_map(save_path: &Path) -> Result<Option<RedirectMap>, Box<dyn Error>> {
         if fs::try_exists(&save_path).await? {
             let resource = fs::read(&save_path).await?;
             match serde_json::from_slice(&resource) {
@@ -72,15 +203,101 @@ impl Output {
         }
     }
 
-    pub async fn save_redirect_map_resource(
+    /**
+     * Update the redirect map or a new one, with the lock not to lose entries added concurrently.
+     */
+    pub async fn update_redirect_map_resource(
         &self,
         domain: &str,
         url_path: &str,
-        resource: &RedirectMap,
+        update: impl FnOnce(&mut RedirectMap),
     ) -> Result<(), Box<dyn Error>> {
         let save_path = self.resource_path.redirect_map_path(domain, url_path);
+        let _guard = self.path_locks.lock(&save_path).await;
+        let mut resource = Self::read_redirect_map(&save_path)
+            .await?
+            .unwrap_or_default();
+        update(&mut resource);
         fs::create_dir_all(save_path.parent().unwrap()).await?;
-        fs::write(&save_path, serde_json::to_vec(resource)?).await?;
+        fs::write(&save_path, serde_json::to_vec(&resource)?).await?;
+        Ok(())
+    }
+
+    pub async fn load_crawl_state<T: DeserializeOwned>(
+        &self,
+        account: &str,
+    ) -> Result<Option<T>, Box<dyn Error>> {
+        let save_path = self.resource_path.crawl_state_path(account);
+        if fs::try_exists(&save_path).await? {
+            Ok(Some(serde_json::from_slice(&fs::read(&save_path).await?)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn save_crawl_state<T: Serialize>(
+        &self,
+        account: &str,
+        state: &T,
+    ) -> Result<(), Box<dyn Error>> {
+        let save_path = self.resource_path.crawl_state_path(account);
+        fs::create_dir_all(save_path.parent().unwrap()).await?;
+        // Write atomically not to break the state by interruption.
+        let tmp_path = save_path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec(state)?).await?;
+        fs::rename(&tmp_path, &save_path).await?;
+        Ok(())
+    }
+
+    /**
+     * Write a file served statically, with its precompressed siblings if enabled and worth it.
+     * Other siblings are removed, since they are served instead of the new content.
+     */
+    async fn write_served_file(
+        &self,
+        save_path: &Path,
+        content: Vec<u8>,
+    ) -> Result<(), Box<dyn Error>> {
+        let _guard = self.path_locks.lock(save_path).await;
+
+        // Compress before writing anything, not to leave stale files on failures.
+        let compressible = self.precompress && compression::is_compressible(save_path);
+        let content = Arc::new(content);
+        let mut siblings = vec![];
+        for encoding in Encoding::ALL {
+            let compressed_opt = if compressible {
+                let source = content.clone();
+                let compressed = tokio::task::spawn_blocking(move || {
+                    encoding
+                        .compress(&source, Level::Best)
+                        .map_err(|err| err.to_string())
+                })
+                .await??;
+                Some(compressed).filter(|compressed| compressed.len() < content.len())
+            } else {
+                None
+            };
+            siblings.push((encoding.sibling_path(save_path), compressed_opt));
+        }
+
+        // Siblings are removed first, so that they never outlive the old content if interrupted.
+        for (sibling_path, _) in &siblings {
+            match fs::remove_file(sibling_path).await {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        fs::write(save_path, content.as_slice()).await?;
+        for (sibling_path, compressed_opt) in siblings {
+            if let Some(compressed) = compressed_opt {
+                // Write atomically, since a partially written sibling would be served as a whole.
+                let mut tmp_path = sibling_path.clone().into_os_string();
+                tmp_path.push(".tmp");
+                fs::write(&tmp_path, compressed).await?;
+                fs::rename(&tmp_path, &sibling_path).await?;
+            }
+        }
         Ok(())
     }
 }
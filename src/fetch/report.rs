@@ -0,0 +1,116 @@
+use std::error::Error;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use super::http::HttpError;
+
+/**
+ * A machine-readable report of a run, to find failed accounts and items to rerun.
+ */
+#[derive(Serialize)]
+pub struct RunReport {
+    pub accounts: Vec<AccountReport>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct AccountReport {
+    pub account: String,
+    /// An error which stopped archiving the account.
+    pub error: Option<String>,
+    pub archived_items_count: usize,
+    pub skipped_items_count: usize,
+    pub failed_items_count: usize,
+    pub failures: Vec<ItemFailure>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ItemFailure {
+    pub id: Option<String>,
+    pub error: String,
+    /// Whether the failure may be resolved by rerunning later.
+    pub transient: bool,
+}
+
+pub struct Reporter {
+    accounts: Mutex<Vec<AccountReport>>,
+}
+
+impl Reporter {
+    pub fn new() -> Self {
+        Self {
+            accounts: Mutex::new(vec![]),
+        }
+    }
+
+    /**
+     * Register an account when it starts, to report it even if no items are changed.
+     */
+    pub fn record_account(&self, account: &str) {
+        self.with_account(account, |_| {});
+    }
+
+    pub fn record_archived(&self, account: &str) {
+        self.with_account(account, |report| report.archived_items_count += 1);
+    }
+
+    pub fn record_skipped(&self, account: &str) {
+        self.with_account(account, |report| report.skipped_items_count += 1);
+    }
+
+    pub fn record_item_failure(
+        &self,
+        account: &str,
+        id: Option<String>,
+        err: &(dyn Error + 'static),
+    ) {
+        self.with_account(account, |report| {
+            report.failed_items_count += 1;
+            report.failures.push(ItemFailure {
+                id,
+                error: err.to_string(),
+                transient: is_transient(err),
+            })
+        });
+    }
+
+    pub fn record_account_failure(&self, account: &str, err: &(dyn Error + 'static)) {
+        self.with_account(account, |report| report.error = Some(err.to_string()));
+    }
+
+    pub fn report(&self) -> RunReport {
+        let mut accounts = match self.accounts.lock() {
+            Err(poisoned) => poisoned.into_inner().clone(),
+            Ok(accounts) => accounts.clone(),
+        };
+        accounts.sort_by(|x, y| x.account.cmp(&y.account));
+        RunReport { accounts }
+    }
+
+    fn with_account<F: FnOnce(&mut AccountReport)>(&self, account: &str, f: F) {
+        let mut accounts = match self.accounts.lock() {
+            Err(poisoned) => poisoned.into_inner(),
+            Ok(accounts) => accounts,
+        };
+        match accounts.iter_mut().find(|report| report.account == account) {
+            Some(report) => f(report),
+            None => {
+                let mut report = AccountReport {
+                    account: account.to_string(),
+                    error: None,
+                    archived_items_count: 0,
+                    skipped_items_count: 0,
+                    failed_items_count: 0,
+                    failures: vec![],
+                };
+                f(&mut report);
+                accounts.push(report);
+            }
+        }
+    }
+}
+
+fn is_transient(err: &(dyn Error + 'static)) -> bool {
+    err.downcast_ref::<HttpError>()
+        .is_some_and(|err| matches!(err, HttpError::Transient { .. }))
+}
@@ -0,0 +1,122 @@
+use std::{error::Error, time::SystemTime};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::rand::SystemRandom;
+use ring::signature::{KeyPair, RsaKeyPair, RSA_PKCS1_SHA256};
+use url::{Position, Url};
+
+/**
+ * Signs requests with HTTP Signatures.
+ *
+ * ref: https://datatracker.ietf.org/doc/html/draft-cavage-http-signatures-12
+ */
+pub struct Signer {
+    key_id: String,
+    key_pair: RsaKeyPair,
+    rng: SystemRandom,
+}
+
+pub struct SignedHeaders {
+    pub host: String,
+    pub date: String,
+    pub signature: String,
+}
+
+impl Signer {
+    pub async fn load(path: &str, key_id: String) -> Result<Self, Box<dyn Error>> {
+        let pem = tokio::fs::read(path).await?;
+        let key_pair = match rustls_pemfile::read_one(&mut pem.as_slice())? {
+            Some(rustls_pemfile::Item::PKCS8Key(der)) => RsaKeyPair::from_pkcs8(&der)?,
+            Some(rustls_pemfile::Item::RSAKey(der)) => RsaKeyPair::from_der(&der)?,
+            _ => return Err(format!("An RSA private key is not found: path={path}").into()),
+        };
+
+        Ok(Self {
+            key_id,
+            key_pair,
+            rng: SystemRandom::new(),
+        })
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub fn public_key_pem(&self) -> String {
+        let der = subject_public_key_info(self.key_pair.public_key().as_ref());
+        let encoded = STANDARD.encode(der);
+
+        let mut pem = "-----BEGIN PUBLIC KEY-----\n".to_string();
+        for line in encoded.as_bytes().chunks(64) {
+            pem.push_str(&String::from_utf8_lossy(line));
+            pem.push('\n');
+        }
+        pem.push_str("-----END PUBLIC KEY-----\n");
+        pem
+    }
+
+    pub fn sign_get(&self, url: &Url) -> Result<SignedHeaders, Box<dyn Error>> {
+        let host = url[Position::BeforeHost..Position::AfterPort].to_string();
+        let date = httpdate::fmt_http_date(SystemTime::now());
+        let request_target = format!("get {}", &url[Position::BeforePath..Position::AfterQuery]);
+
+        let signing_string =
+            format!("(request-target): {request_target}\nhost: {host}\ndate: {date}");
+        let mut signature = vec![0; self.key_pair.public().modulus_len()];
+        self.key_pair.sign(
+            &RSA_PKCS1_SHA256,
+            &self.rng,
+            signing_string.as_bytes(),
+            &mut signature,
+        )?;
+
+        Ok(SignedHeaders {
+            host,
+            date,
+            signature: format!(
+                "keyId=\"{}\",algorithm=\"rsa-sha256\",headers=\"(request-target) host date\",signature=\"{}\"",
+                self.key_id,
+                STANDARD.encode(signature),
+            ),
+        })
+    }
+}
+
+/**
+ * Wraps a PKCS#1 RSAPublicKey into a SubjectPublicKeyInfo, which is expected by most of servers.
+ *
+ * ref: https://datatracker.ietf.org/doc/html/rfc5280#section-4.1
+ */
+fn subject_public_key_info(rsa_public_key: &[u8]) -> Vec<u8> {
+    // AlgorithmIdentifier { algorithm: rsaEncryption, parameters: NULL }
+    let algorithm = [
+        0x30, 0x0d, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01, 0x05, 0x00,
+    ];
+
+    let mut bit_string = vec![0x00];
+    bit_string.extend_from_slice(rsa_public_key);
+
+    let mut content = algorithm.to_vec();
+    content.extend(der_tlv(0x03, &bit_string));
+
+    der_tlv(0x30, &content)
+}
+
+fn der_tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut result = vec![tag];
+    let len = value.len();
+    if len < 0x80 {
+        result.push(len as u8);
+    } else {
+        let len_bytes: Vec<u8> = len
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|x| *x == 0)
+            .collect();
+        result.push(0x80 | len_bytes.len() as u8);
+        result.extend(len_bytes);
+    }
+    result.extend_from_slice(value);
+    result
+}
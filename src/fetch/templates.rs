@@ -17,6 +17,8 @@ pub struct ProfileHtmlParams {
     pub summary: Option<String>,
     pub summary_map: HashMap<String, String>,
     pub url: Option<String>,
+    pub icon: Option<String>,
+    pub image: Option<String>,
     pub moved_to: Option<String>,
     pub published: Option<String>,
 }
@@ -30,6 +32,7 @@ pub struct ObjectHtmlParams {
     pub to: Option<String>,
     pub content: Option<String>,
     pub content_map: HashMap<String, String>,
+    pub attachments: Vec<String>,
     pub url: Option<String>,
     pub published: Option<String>,
 }
@@ -48,7 +51,7 @@ impl<'a> Templates<'a> {
     pub fn create() -> Result<Self, Box<dyn Error>> {
         let mut handlebars = Handlebars::new();
 
-        handlebars.register_template_string(TEMPLATE_KEY_PROFILE_HTML, &[
+        handlebars.register_template_string(TEMPLATE_KEY_PROFILE_HTML, [
             "<!DOCTYPE html>",
             "<html>",
             "<head>",
@@ -69,13 +72,15 @@ impl<'a> Templates<'a> {
             "{{#if summary}}<dt>Summary</dt><dd>{{{summary}}}</dd>{{/if}}",
             "{{#each summary_map}}<dt>Summary ({{@key}})</dt><dd>{{this}}</dd>{{/each}}",
             "{{#if url}}<dt>URL</dt><dd><a href=\"{{url}}\">{{url}}</a></dd>{{/if}}",
+            "{{#if icon}}<dt>Icon</dt><dd><img alt=\"Icon\" src=\"{{icon}}\"></dd>{{/if}}",
+            "{{#if image}}<dt>Header</dt><dd><img alt=\"Header\" src=\"{{image}}\"></dd>{{/if}}",
             "{{#if published}}<dt>Published</dt><dd>{{published}}</dd>{{/if}}",
             "</dl>",
             "</body>",
             "</html>",
         ].join(""))?;
 
-        handlebars.register_template_string(TEMPLATE_KEY_OBJECT_HTML, &[
+        handlebars.register_template_string(TEMPLATE_KEY_OBJECT_HTML, [
             "<!DOCTYPE html>",
             "<html>",
             "<head>",
@@ -90,6 +95,7 @@ impl<'a> Templates<'a> {
             "<dl>",
             "{{#if content}}<dt>Content</dt><dd>{{{content}}}</dd>{{/if}}",
             "{{#each content_map}}<dt>Content ({{@key}})</dt><dd>{{{this}}}</dd>{{/each}}",
+            "{{#each attachments}}<dt>Attachment</dt><dd><a href=\"{{this}}\">{{this}}</a></dd>{{/each}}",
             "{{#if from}}<dt>From</dt><dd><a href=\"{{account_url}}\">{{account}}</a></dd>{{/if}}",
             "{{#if to}}<dt>To</dt><dd><a href=\"{{to}}\">{{to}}</a></dd>{{/if}}",
             "{{#if url}}<dt>URL</dt><dd><a href=\"{{url}}\">{{url}}</a></dd>{{/if}}",
@@ -99,7 +105,7 @@ impl<'a> Templates<'a> {
             "</html>",
         ].join(""))?;
 
-        handlebars.register_template_string(TEMPLATE_KEY_TOP_HTML, &[
+        handlebars.register_template_string(TEMPLATE_KEY_TOP_HTML, [
             "<!DOCTYPE html>",
             "<html>",
             "<head>",
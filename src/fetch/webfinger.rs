@@ -3,11 +3,13 @@ use std::error::Error;
 use reqwest::StatusCode;
 use serde::de::DeserializeOwned;
 
-use archivedon::webfinger::resource::Resource as WebfingerResource;
+use crate::webfinger::resource::Resource as WebfingerResource;
 use url::Url;
 
+use super::http::{HttpClient, HttpError};
+
 pub async fn fetch_ap_account_actor_url(
-    client: &reqwest::Client,
+    client: &HttpClient,
     domain: &str,
     subject: &str,
 ) -> Result<String, Box<dyn Error>> {
@@ -46,25 +48,31 @@ pub async fn fetch_ap_account_actor_url(
 }
 
 pub async fn fetch_webfinger_resource<T: DeserializeOwned>(
-    client: &reqwest::Client,
+    client: &HttpClient,
     uri: &str,
 ) -> Result<T, Box<dyn Error>> {
-    let current_uri: &str = uri;
-    loop {
-        let response = client.get(current_uri).send().await?;
-        match response.status() {
-            StatusCode::OK => {
-                // continue
-            }
-            x @ (StatusCode::FOUND | StatusCode::TEMPORARY_REDIRECT) => {
-                // TODO: support redirection
-                return Err(format!("Not supported redirection: status={x}").into());
+    let response = client.get(uri, None).await?;
+    match response.status {
+        StatusCode::OK => {
+            // continue
+        }
+        x => {
+            return Err(HttpError::Permanent {
+                url: response.url,
+                reason: format!("Unknown response: status={x}"),
             }
-            x => return Err(format!("Unknown response: status={x}").into()),
+            .into())
         }
+    }
 
-        let data: T = response.json().await?;
-
-        return Ok(data);
+    if !response.redirected_from.is_empty() {
+        println!(
+            "Info: WebFinger resource is redirected: from={uri}, to={}",
+            response.url
+        );
     }
+
+    let data: T = serde_json::from_slice(&response.body)?;
+
+    Ok(data)
 }
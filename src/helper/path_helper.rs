@@ -4,6 +4,7 @@ use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use base64::Engine;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use sha2::{Digest, Sha256};
 
 pub fn to_url_safe(base: &Path, domain: &str, url_path: &str, ext: &str) -> PathBuf {
     let mut path = base.join(component_to_url_safe(domain));
@@ -31,6 +32,9 @@ pub fn to_url_safe(base: &Path, domain: &str, url_path: &str, ext: &str) -> Path
 static RE_SAFE_COMPONENT: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^([a-zA-Z0-9-_:@]|\.[a-zA-Z0-9-_:@])[a-zA-Z0-9-_.:@]*$").unwrap());
 
+/// File names are limited to 255 bytes on most file systems, which leaves room for `.json`.
+const MAX_COMPONENT_LEN: usize = 250;
+
 pub fn component_to_url_safe(component: &str) -> String {
     let main = if RE_SAFE_COMPONENT.is_match(component) {
         component.to_string()
@@ -38,7 +42,13 @@ pub fn component_to_url_safe(component: &str) -> String {
         format!("..{}", URL_SAFE_NO_PAD.encode(component))
     };
 
-    format!("_{main}")
+    let safe_component = format!("_{main}");
+    if safe_component.len() <= MAX_COMPONENT_LEN {
+        return safe_component;
+    }
+
+    // Base64 never contains `.`, so hashed names never conflict with others.
+    format!("_...{:x}", Sha256::digest(component.as_bytes()))
 }
 
 pub fn component_to_url_safe_with_safe_ext(component: &str, ext: &str) -> String {
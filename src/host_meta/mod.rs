@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+/**
+ * ref: https://datatracker.ietf.org/doc/html/rfc6415
+ */
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct HostMeta {
+    pub links: Vec<Link>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct Link {
+    pub rel: String,
+    #[serde(rename = "type")]
+    pub typ: Option<String>,
+    pub href: Option<String>,
+    pub template: Option<String>,
+}
+
+impl HostMeta {
+    /**
+     * A host-meta pointing to the WebFinger endpoint with a LRDD template.
+     */
+    pub fn webfinger(scheme: &str, host: &str) -> Self {
+        Self {
+            links: vec![Link {
+                rel: "lrdd".to_string(),
+                typ: Some("application/jrd+json".to_string()),
+                href: None,
+                template: Some(format!(
+                    "{scheme}://{host}/.well-known/webfinger?resource={{uri}}"
+                )),
+            }],
+        }
+    }
+
+    /**
+     * Serialize as XRD.
+     */
+    pub fn to_xrd(&self) -> String {
+        let mut xrd = String::new();
+        xrd.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xrd.push_str("<XRD xmlns=\"http://docs.oasis-open.org/ns/xri/xrd-1.0\">\n");
+        for link in &self.links {
+            xrd.push_str(&format!("  <Link rel=\"{}\"", escape_xml(&link.rel)));
+            if let Some(typ) = &link.typ {
+                xrd.push_str(&format!(" type=\"{}\"", escape_xml(typ)));
+            }
+            if let Some(href) = &link.href {
+                xrd.push_str(&format!(" href=\"{}\"", escape_xml(href)));
+            }
+            if let Some(template) = &link.template {
+                xrd.push_str(&format!(" template=\"{}\"", escape_xml(template)));
+            }
+            xrd.push_str("/>\n");
+        }
+        xrd.push_str("</XRD>\n");
+        xrd
+    }
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
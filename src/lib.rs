@@ -1,5 +1,12 @@
+pub mod compression;
+pub mod content_negotiation;
+pub mod entity_tag;
+pub mod fetch;
 pub mod helper;
+pub mod host_meta;
+pub mod manifest;
 pub mod nodeinfo;
 pub mod redirect_map;
 pub mod resource_path;
+pub mod server;
 pub mod webfinger;
@@ -1,8 +1,9 @@
 use std::error::Error;
+use std::time::Duration;
 
 use clap::Parser;
 
-mod fetch;
+use archivedon::fetch::{self, ObjectPathRuleKind, RedirectPolicy};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -15,10 +16,57 @@ struct Cli {
     output: String,
     #[arg(long, default_value_t = false)]
     fetch_outbox: bool,
+    #[arg(long, default_value_t = false)]
+    fetch_media: bool,
     #[arg(long, default_value_t = 1000)]
     default_max_pages: usize,
     #[arg(long, default_value_t = 5)]
     page_items_count: usize,
+    /// A policy to follow HTTP redirections.
+    #[arg(long, value_enum, default_value_t = RedirectPolicy::Any)]
+    redirect_policy: RedirectPolicy,
+    /// A maximum number of HTTP redirections to follow per request.
+    #[arg(long, default_value_t = 5)]
+    max_redirects: usize,
+    /// A path of PEM encoded RSA private key to sign requests with HTTP Signatures.
+    #[arg(long)]
+    signing_key: Option<String>,
+    /// Rules to map object IDs into archived paths, which are tried in order.
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = ObjectPathRuleKind::all())]
+    object_path_rules: Vec<ObjectPathRuleKind>,
+    /// A maximum number of resources to fetch concurrently.
+    #[arg(long, default_value_t = 8)]
+    concurrency: usize,
+    /// A maximum number of concurrent requests to the same host.
+    #[arg(long, default_value_t = 2)]
+    max_requests_per_host: usize,
+    /// A minimum interval in milliseconds between requests to the same host.
+    #[arg(long, default_value_t = 100)]
+    min_request_interval_ms: u64,
+    /// A maximum number of retries for transient failures, e.g. 429, 5xx and timeouts.
+    #[arg(long, default_value_t = 3)]
+    max_retries: usize,
+    /// An initial backoff in milliseconds before retrying, which is doubled per retry.
+    #[arg(long, default_value_t = 1000)]
+    initial_retry_backoff_ms: u64,
+    /// A maximum backoff in milliseconds before retrying. Requests fail if `Retry-After` exceeds it.
+    #[arg(long, default_value_t = 300000)]
+    max_retry_backoff_ms: u64,
+    /// A timeout in seconds per request.
+    #[arg(long, default_value_t = 30)]
+    request_timeout_secs: u64,
+    /// Ignore the crawl state of previous runs, and fetch outboxes from scratch.
+    #[arg(long, default_value_t = false)]
+    ignore_crawl_state: bool,
+    /// Skip accounts and items which fail to be archived, instead of stopping the run.
+    #[arg(long, default_value_t = false)]
+    continue_on_error: bool,
+    /// A path to write a JSON report of archived, skipped and failed items per account.
+    #[arg(long)]
+    report: Option<String>,
+    /// Write gzip and brotli compressed siblings of text files to be served precompressed.
+    #[arg(long, default_value_t = false)]
+    precompress: bool,
 }
 
 #[tokio::main]
@@ -28,9 +76,29 @@ async fn main() -> Result<(), Box<dyn Error>> {
     fetch::run(
         &cli.input,
         &cli.output,
-        cli.fetch_outbox,
-        cli.default_max_pages,
-        cli.page_items_count,
+        fetch::Options {
+            fetch_outbox: cli.fetch_outbox,
+            fetch_media: cli.fetch_media,
+            default_max_pages: cli.default_max_pages,
+            page_items_count: cli.page_items_count,
+            http: fetch::HttpClientOptions {
+                redirect_policy: cli.redirect_policy,
+                max_redirects: cli.max_redirects,
+                max_requests_per_host: cli.max_requests_per_host,
+                min_request_interval: Duration::from_millis(cli.min_request_interval_ms),
+                max_retries: cli.max_retries,
+                initial_retry_backoff: Duration::from_millis(cli.initial_retry_backoff_ms),
+                max_retry_backoff: Duration::from_millis(cli.max_retry_backoff_ms),
+                request_timeout: Duration::from_secs(cli.request_timeout_secs),
+            },
+            signing_key_path: cli.signing_key,
+            object_path_rules: cli.object_path_rules,
+            concurrency: cli.concurrency,
+            use_crawl_state: !cli.ignore_crawl_state,
+            continue_on_error: cli.continue_on_error,
+            report_path: cli.report,
+            precompress: cli.precompress,
+        },
     )
     .await?;
 
@@ -1,9 +1,10 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use log::LevelFilter;
 use std::error::Error;
+use std::path::PathBuf;
 
-mod server;
-
-use server::cmd;
+use archivedon::server::cmd;
+use archivedon::server::config::{Config, TlsConfig};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -17,37 +18,154 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    Serve {
-        /// A bind IP address to listen.
-        #[arg(long, env = "ADDR")]
-        addr: Option<String>,
-
-        /// A bind port number to listen.
-        #[arg(short, long, env = "PORT")]
-        port: u16,
-
-        /// A path of resource directory to serve.
-        #[arg(long, env = "RESOURCE_DIR")]
-        resource_dir: String,
-
-        /// An URL which the server expose.
-        #[arg(long, env = "EXPOSE_URL_BASE")]
-        expose_url_base: String,
-    },
+    Serve(ServeArgs),
+}
+
+/**
+ * Options of `serve`, which override the configuration file.
+ */
+#[derive(Args)]
+struct ServeArgs {
+    /// A configuration file in TOML or YAML.
+    #[arg(short, long, env = "CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Bind IP addresses to listen.
+    #[arg(long, env = "ADDR", value_delimiter = ',')]
+    addr: Vec<String>,
+
+    /// A bind port number to listen.
+    #[arg(short, long, env = "PORT")]
+    port: Option<u16>,
+
+    /// A path of resource directory to serve.
+    #[arg(long, env = "RESOURCE_DIR")]
+    resource_dir: Option<PathBuf>,
+
+    /// An URL which the server expose.
+    #[arg(long, env = "EXPOSE_URL_BASE")]
+    expose_url_base: Option<String>,
+
+    /// Show a link to the top page in Gone pages for browsers.
+    #[arg(long, env = "GONE_TOP_LINK", num_args = 0..=1, default_missing_value = "true")]
+    gone_top_link: Option<bool>,
+
+    /// A name of the node for NodeInfo.
+    #[arg(long, env = "NODE_NAME")]
+    node_name: Option<String>,
+
+    /// A description of the node for NodeInfo.
+    #[arg(long, env = "NODE_DESCRIPTION")]
+    node_description: Option<String>,
+
+    /// A name of the maintainer for NodeInfo.
+    #[arg(long, env = "MAINTAINER_NAME")]
+    maintainer_name: Option<String>,
+
+    /// An email of the maintainer for NodeInfo.
+    #[arg(long, env = "MAINTAINER_EMAIL")]
+    maintainer_email: Option<String>,
+
+    /// Index redirect maps and WebFinger resources in memory, which are reloaded on SIGHUP.
+    #[arg(long, env = "INDEX", num_args = 0..=1, default_missing_value = "true")]
+    index: Option<bool>,
+
+    /// Files indexed at most by `--index`, and the rest are read from the disk. [default: 100000]
+    #[arg(long, env = "INDEX_MAX_ENTRIES")]
+    index_max_entries: Option<usize>,
+
+    /// An interval in seconds to check updates of the archive by its manifest, and reload it.
+    #[arg(long, env = "RELOAD_INTERVAL_SECS")]
+    reload_interval_secs: Option<u64>,
+
+    /// PEM files of TLS certificate chains, which are selected by SNI. Serve HTTPS if given.
+    #[arg(long, env = "TLS_CERT", value_delimiter = ',')]
+    tls_cert: Vec<PathBuf>,
+
+    /// PEM files of TLS private keys, in the same order as `--tls-cert`.
+    #[arg(long, env = "TLS_KEY", value_delimiter = ',')]
+    tls_key: Vec<PathBuf>,
+
+    /// A timeout in seconds to wait in-flight requests on SIGTERM or SIGINT. [default: 30]
+    #[arg(long, env = "DRAIN_TIMEOUT_SECS")]
+    drain_timeout_secs: Option<u64>,
+
+    /// Serve metrics in the Prometheus text format on `/metrics`.
+    #[arg(long, env = "METRICS", num_args = 0..=1, default_missing_value = "true")]
+    metrics: Option<bool>,
+
+    /// An address like `127.0.0.1:9090` to serve `/healthz`, `/readyz` and `/metrics` only on.
+    /// Otherwise, they are served only for hosts of expose URLs.
+    #[arg(long, env = "ADMIN_ADDR")]
+    admin_addr: Option<String>,
+}
+
+impl ServeArgs {
+    fn into_config(self) -> Result<Config, Box<dyn Error>> {
+        let mut config = match &self.config {
+            Some(path) => Config::load(path)?,
+            None => Config::default(),
+        };
+
+        if !self.addr.is_empty() {
+            config.addrs = self.addr;
+        }
+        override_with(&mut config.port, self.port);
+        override_with(&mut config.resource_dir, self.resource_dir);
+        override_with(&mut config.expose_url_base, self.expose_url_base);
+        override_with(&mut config.gone_top_link, self.gone_top_link);
+        override_with(&mut config.node.name, self.node_name);
+        override_with(&mut config.node.description, self.node_description);
+        override_with(&mut config.node.maintainer_name, self.maintainer_name);
+        override_with(&mut config.node.maintainer_email, self.maintainer_email);
+        override_with(&mut config.index, self.index);
+        override_with(&mut config.index_max_entries, self.index_max_entries);
+        override_with(&mut config.reload_interval_secs, self.reload_interval_secs);
+        override_with(&mut config.drain_timeout_secs, self.drain_timeout_secs);
+        override_with(&mut config.metrics, self.metrics);
+        override_with(&mut config.admin_addr, self.admin_addr);
+
+        if self.tls_cert.len() != self.tls_key.len() {
+            return Err("The numbers of --tls-cert and --tls-key are mismatched.".into());
+        }
+        if !self.tls_cert.is_empty() {
+            config.tls = self
+                .tls_cert
+                .into_iter()
+                .zip(self.tls_key)
+                .map(|(cert, key)| TlsConfig { cert, key })
+                .collect();
+        }
+
+        Ok(config)
+    }
+}
+
+fn override_with<T>(target: &mut Option<T>, value: Option<T>) {
+    if value.is_some() {
+        *target = value;
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
 
-    match &cli.command {
-        Commands::Serve {
-            addr,
-            port,
-            resource_dir,
-            expose_url_base,
-            ..
-        } => cmd::serve::run(addr, *port, resource_dir, expose_url_base),
+    let log_level = match cli.debug {
+        0 => LevelFilter::Info,
+        1 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    env_logger::Builder::new()
+        .filter_level(log_level)
+        .parse_default_env()
+        .init();
+
+    match cli.command {
+        Commands::Serve(args) => {
+            let options = args.into_config()?.into_options()?;
+            cmd::serve::run(options)
+        }
     }
     .await?;
 
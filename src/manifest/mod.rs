@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/**
+ * A summary of the archive, which is written by `archivedon-fetch` and read by the server.
+ */
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct Manifest {
+    pub updated_at: DateTime<Utc>,
+    pub accounts: Vec<ManifestAccount>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ManifestAccount {
+    /// An account like `user@example.com`.
+    pub account: String,
+    pub actor_url: String,
+    pub posts_count: usize,
+    pub archived_at: DateTime<Utc>,
+}
+
+impl Manifest {
+    pub fn new(updated_at: DateTime<Utc>) -> Self {
+        Self {
+            updated_at,
+            accounts: vec![],
+        }
+    }
+
+    /**
+     * Add or replace the account, keeping accounts sorted.
+     */
+    pub fn upsert_account(&mut self, account: ManifestAccount) {
+        match self
+            .accounts
+            .binary_search_by(|x| x.account.cmp(&account.account))
+        {
+            Ok(index) => self.accounts[index] = account,
+            Err(index) => self.accounts.insert(index, account),
+        }
+    }
+
+    pub fn account(&self, account: &str) -> Option<&ManifestAccount> {
+        self.accounts
+            .binary_search_by(|x| x.account.as_str().cmp(account))
+            .ok()
+            .map(|index| &self.accounts[index])
+    }
+
+    pub fn users_count(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn posts_count(&self) -> usize {
+        self.accounts
+            .iter()
+            .map(|account| account.posts_count)
+            .sum()
+    }
+}
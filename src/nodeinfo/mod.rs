@@ -15,8 +15,58 @@ pub struct DiscoveryItem {
     pub href: String,
 }
 
+/**
+ * Supported schema versions of NodeInfo.
+ */
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Version {
+    V2_0,
+    V2_1,
+}
+
+impl Version {
+    /**
+     * Versions listed in discovery, in the order of preference.
+     *
+     * 1.x are not supported, since their schemas limit `software.name` and `protocols` to
+     * a fixed set without this software nor ActivityPub.
+     */
+    pub const ALL: [Version; 2] = [Version::V2_1, Version::V2_0];
+
+    pub fn parse(version: &str) -> Option<Self> {
+        match version {
+            "2.0" => Some(Version::V2_0),
+            "2.1" => Some(Version::V2_1),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Version::V2_0 => "2.0",
+            Version::V2_1 => "2.1",
+        }
+    }
+
+    /**
+     * A schema URL, which is used as `rel` of discovery links.
+     */
+    pub fn schema_url(&self) -> String {
+        format!(
+            "http://nodeinfo.diaspora.software/ns/schema/{}",
+            self.as_str()
+        )
+    }
+
+    pub fn content_type(&self) -> String {
+        format!("application/json; profile=\"{}#\"", self.schema_url())
+    }
+}
+
 /**
  * ref: https://nodeinfo.diaspora.software/ns/schema/2.1
+ *
+ * Also used for 2.0, which has no `repository` and `homepage` of software.
  */
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct NodeInfo {
@@ -3,11 +3,19 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-#[derive(Serialize, Deserialize)]
+use crate::content_negotiation::accept::Accept;
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct RedirectMap {
     type_to_url: HashMap<String, String>,
 }
 
+impl Default for RedirectMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RedirectMap {
     pub fn new() -> RedirectMap {
         RedirectMap {
@@ -15,17 +23,42 @@ impl RedirectMap {
         }
     }
 
-    pub fn insert_entry(&mut self, typ: String, new_url: &Url) -> () {
+    pub fn insert_entry(&mut self, typ: String, new_url: &Url) {
         let _: Option<String> = self.type_to_url.insert(typ, new_url.to_string());
     }
 
     pub fn get_entry(&self, typ: &str) -> Option<Url> {
         match self.type_to_url.get(typ) {
             None => None,
-            Some(url) => match Url::parse(url) {
-                Ok(url) => Some(url),
-                Err(_) => None,
-            },
+            Some(url) => Url::parse(url).ok(),
+        }
+    }
+
+    /**
+     * Choose the redirection for the Accept header.
+     *
+     * Requests without explicitly acceptable types fall back to the entry for any types.
+     */
+    pub fn negotiate(&self, accept_opt: Option<&str>) -> Option<Url> {
+        let accept = match accept_opt {
+            None => return self.get_entry("*/*"),
+            Some(accept) => Accept::parse(accept),
+        };
+
+        let mut types: Vec<&str> = self
+            .type_to_url
+            .keys()
+            .map(|typ| typ.as_str())
+            .filter(|typ| *typ != "*/*")
+            .collect();
+        types.sort();
+
+        if let Some(typ) = accept.negotiate_explicitly(&types) {
+            return self.get_entry(typ);
+        }
+        if let Some(url) = self.get_entry("*/*") {
+            return Some(url);
         }
+        accept.negotiate(&types).and_then(|typ| self.get_entry(typ))
     }
 }
@@ -4,18 +4,24 @@ use crate::helper::path_helper;
 
 pub struct ResourcePath {
     pub index_html_path: PathBuf,
-    webfinger_root_dir: PathBuf,
+    pub manifest_path: PathBuf,
+    pub webfinger_root_dir: PathBuf,
+    pub webfinger_alias_root_dir: PathBuf,
     pub static_root_dir: PathBuf,
-    redirect_map_root_dir: PathBuf,
+    pub redirect_map_root_dir: PathBuf,
+    crawl_state_root_dir: PathBuf,
 }
 
 impl ResourcePath {
     pub fn new(root_dir: PathBuf) -> Self {
         Self {
             index_html_path: root_dir.join("index.html"),
+            manifest_path: root_dir.join("manifest.json"),
             webfinger_root_dir: root_dir.join("webfinger"),
+            webfinger_alias_root_dir: root_dir.join("webfinger_alias"),
             static_root_dir: root_dir.join("static"),
             redirect_map_root_dir: root_dir.join("map"),
+            crawl_state_root_dir: root_dir.join("crawl_state"),
         }
     }
 
@@ -26,6 +32,20 @@ impl ResourcePath {
         ))
     }
 
+    pub fn webfinger_alias_path(&self, alias: &str) -> PathBuf {
+        self.webfinger_alias_root_dir.join(format!(
+            "{}.json",
+            path_helper::component_to_url_safe(alias)
+        ))
+    }
+
+    pub fn crawl_state_path(&self, account: &str) -> PathBuf {
+        self.crawl_state_root_dir.join(format!(
+            "{}.json",
+            path_helper::component_to_url_safe(account)
+        ))
+    }
+
     pub fn redirect_map_path(&self, domain: &str, url_path: &str) -> PathBuf {
         path_helper::to_url_safe(&self.redirect_map_root_dir, domain, url_path, "json")
     }
@@ -1,76 +1,469 @@
+use log::{error, info, warn};
 use std::{
+    collections::HashMap,
     error::Error,
+    future::Future,
     net::{IpAddr, SocketAddr},
-    path::Path,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::watch,
+};
+use warp::{
+    filters::path::FullPath,
+    filters::BoxedFilter,
+    http::{
+        header::{ACCESS_CONTROL_ALLOW_ORIGIN, VARY},
+        HeaderValue, Method,
+    },
+    reply::Response,
+    Filter, Reply,
 };
-use url::Url;
-use warp::Filter;
 
-use crate::server::env::Env;
-use crate::server::handler;
+use crate::server::env::{site::SiteOptions, CacheOptions, Env, HostOptions};
+use crate::server::handler::{self, Routed};
+use crate::server::tls::{self, CertResolver, CertificateFile};
 
-pub async fn run(
-    addr_opt: &Option<String>,
-    port: u16,
-    resource_dir: &str,
-    expose_url_base: &str,
-) -> Result<(), Box<dyn Error>> {
-    let expose_url_base = Url::parse(expose_url_base)?;
-    let env = Env::load(Path::new(resource_dir), expose_url_base);
+pub struct Options {
+    pub addrs: Vec<IpAddr>,
+    pub port: u16,
+    /// A site for requests to hosts which no site is configured for.
+    pub default_site: Option<SiteOptions>,
+    pub sites: Vec<SiteOptions>,
+    /// Index up to this number of redirect maps and WebFinger resources in memory if given.
+    pub index_max_entries: Option<usize>,
+    /// An interval to check updates of the archive and certificates, in addition to SIGHUP.
+    pub reload_interval: Option<Duration>,
+    /// Serve HTTPS with these certificates if given.
+    pub tls_certificates: Vec<CertificateFile>,
+    /// A timeout to wait in-flight requests on shutdown.
+    pub drain_timeout: Duration,
+    /// Serve metrics in the Prometheus text format on `/metrics`.
+    pub metrics: bool,
+    /// Serve `/healthz`, `/readyz` and `/metrics` only on this address if given, or otherwise
+    /// only for hosts of expose URLs, so that they never hide archived paths of old domains.
+    pub admin_addr: Option<SocketAddr>,
+    pub cors: CorsOptions,
+    pub cache: CacheOptions,
+    /// Behavior per host, keyed by lowercased host names.
+    pub hosts: HashMap<String, HostOptions>,
+}
 
-    let addr = match addr_opt {
-        Some(addr_raw) => addr_raw.parse()?,
-        None => IpAddr::from([0, 0, 0, 0]),
+pub struct CorsOptions {
+    /// Allowed origins, or `*` for any origin.
+    pub allow_origins: Vec<String>,
+}
+
+pub async fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let env = Env::load(
+        options.default_site,
+        options.sites,
+        options.hosts,
+        options.cache,
+        options.index_max_entries,
+    )?;
+
+    let cert_resolver = if options.tls_certificates.is_empty() {
+        None
+    } else {
+        Some(CertResolver::load(options.tls_certificates)?)
     };
-    let sock_addr = SocketAddr::new(addr, port);
 
-    let static_dir = env.resource_path.static_root_dir.clone();
-    let index_html_path = env.resource_path.index_html_path.clone();
+    spawn_reloader(env.clone(), cert_resolver.clone(), options.reload_interval)?;
+
+    let shutdown_env = env.clone();
+    let access_env = env.clone();
+    let cors = Arc::new(options.cors);
+    let site_files = site_files(&env);
+    let on_expose_host = on_expose_host(&env);
     let with_env = move || env.clone();
 
-    let top = warp::get()
-        .and(warp::path::end())
-        .and(warp::fs::file(index_html_path));
+    let healthz = warp::get()
+        .and(warp::path!("healthz"))
+        .and_then(handler::health::handle_healthz)
+        .map(|reply| Routed::new("healthz", reply));
+
+    let readyz = warp::get()
+        .and(warp::path!("readyz"))
+        .map(with_env.clone())
+        .and_then(handler::health::handle_readyz)
+        .map(|reply| Routed::new("readyz", reply));
 
     let webfinger = warp::get()
         .and(warp::path!(".well-known" / "webfinger"))
         .map(with_env.clone())
+        .and(warp::header::optional("host"))
+        .and(warp::header::optional("accept-encoding"))
+        .and(warp::header::optional("if-none-match"))
+        .and(warp::header::optional("if-modified-since"))
         .and(warp::query::<Vec<(String, String)>>())
-        .and_then(handler::webfinger::handle);
+        .and_then(handler::webfinger::handle)
+        .map(|reply| Routed::new("webfinger", reply));
+
+    let host_meta_xrd = warp::get()
+        .and(warp::path!(".well-known" / "host-meta"))
+        .map(with_env.clone())
+        .and(warp::header::optional("host"))
+        .and_then(handler::host_meta::handle_xrd)
+        .map(|reply| Routed::new("host_meta", reply));
+
+    let host_meta_json = warp::get()
+        .and(warp::path!(".well-known" / "host-meta.json"))
+        .map(with_env.clone())
+        .and(warp::header::optional("host"))
+        .and_then(handler::host_meta::handle_json)
+        .map(|reply| Routed::new("host_meta", reply));
+
+    let metrics = warp::get()
+        .and(warp::path!("metrics"))
+        .and(enabled(options.metrics))
+        .map(with_env.clone())
+        .and_then(handler::metrics::handle)
+        .map(|reply| Routed::new("metrics", reply));
 
     let nodeinfo_discovery = warp::get()
         .and(warp::path!(".well-known" / "nodeinfo"))
         .map(with_env.clone())
-        .and_then(handler::nodeinfo::handle_discovery);
+        .and(warp::header::optional("host"))
+        .and(warp::header::optional("accept-encoding"))
+        .and_then(handler::nodeinfo::handle_discovery)
+        .map(|reply| Routed::new("nodeinfo", reply));
 
     let nodeinfo_resource = warp::get()
+        .map(with_env.clone())
+        .and(warp::header::optional("host"))
+        .and(warp::header::optional("accept-encoding"))
         .and(warp::path!("archivedon" / "nodeinfo" / String))
-        .and_then(handler::nodeinfo::handle_resource);
-
-    let static_resource = warp::path("static").and(warp::fs::dir(static_dir));
+        .and_then(handler::nodeinfo::handle_resource)
+        .map(|reply| Routed::new("nodeinfo", reply));
 
     let redirect_map = warp::get()
         .map(with_env.clone())
         .and(warp::header("host"))
         .and(warp::filters::path::full())
         .and(warp::header::optional("accept"))
-        .and_then(handler::redirect_map::handle);
+        .and_then(handler::redirect_map::handle)
+        .map(|reply| Routed::new("redirect", reply));
+
+    let gone_get = warp::get()
+        .map(with_env.clone())
+        .and(warp::header::optional("host"))
+        .and(warp::filters::path::full())
+        .and(warp::header::optional("accept"))
+        .and_then(handler::gone::handle)
+        .map(|reply| Routed::new("gone", reply));
 
-    let gone_get = warp::get().map(|| handler::gone());
+    let gone_post = warp::post()
+        .map(handler::gone)
+        .map(|reply| Routed::new("gone", reply));
 
-    let gone_post = warp::post().map(|| handler::gone());
+    let unmatched = warp::any().map(|| Routed::new("unmatched", handler::method_not_allowed()));
 
-    let service = top
+    let admin_routes = healthz.or(readyz).unify().or(metrics).unify();
+
+    let routes = site_files
+        .or(enabled(options.admin_addr.is_none())
+            .and(on_expose_host)
+            .and(admin_routes.clone()))
+        .unify()
         .or(webfinger)
+        .unify()
+        .or(host_meta_xrd)
+        .unify()
+        .or(host_meta_json)
+        .unify()
         .or(nodeinfo_discovery)
+        .unify()
         .or(nodeinfo_resource)
-        .or(static_resource)
+        .unify()
         .or(redirect_map)
+        .unify()
         .or(gone_get)
-        .or(gone_post);
+        .unify()
+        .or(gone_post)
+        .unify()
+        .or(unmatched)
+        .unify();
+
+    let service = with_access_log(access_env.clone(), cors.clone(), routes.boxed());
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(());
+    let mut servers: Vec<Pin<Box<dyn Future<Output = ()>>>> = vec![];
+    for addr in options.addrs {
+        let sock_addr = SocketAddr::new(addr, options.port);
+        let mut shutdown_rx = shutdown_rx.clone();
+        let shutdown_signal = async move {
+            let _ = shutdown_rx.changed().await;
+        };
+        match &cert_resolver {
+            None => {
+                let (_, server) = warp::serve(service.clone())
+                    .try_bind_with_graceful_shutdown(sock_addr, shutdown_signal)?;
+                servers.push(Box::pin(server));
+            }
+            Some(cert_resolver) => {
+                let incoming = tls::incoming(sock_addr, cert_resolver.clone()).await?;
+                let server = warp::serve(service.clone())
+                    .serve_incoming_with_graceful_shutdown(incoming, shutdown_signal);
+                servers.push(Box::pin(server));
+            }
+        }
+    }
+
+    if let Some(admin_addr) = options.admin_addr {
+        let admin_unmatched = warp::any().map(|| Routed::new("unmatched", handler::not_found()));
+        let admin_service = with_access_log(
+            access_env,
+            cors,
+            admin_routes.or(admin_unmatched).unify().boxed(),
+        );
+        let mut shutdown_rx = shutdown_rx.clone();
+        let shutdown_signal = async move {
+            let _ = shutdown_rx.changed().await;
+        };
+        let (_, server) = warp::serve(admin_service)
+            .try_bind_with_graceful_shutdown(admin_addr, shutdown_signal)?;
+        servers.push(Box::pin(server));
+    }
+
+    let servers = async move {
+        futures_util::future::join_all(servers).await;
+    };
+    serve_until_shutdown(servers, shutdown_env, shutdown_tx, options.drain_timeout).await?;
+
+    Ok(())
+}
+
+/**
+ * Top pages and static files of sites, selected by the Host header.
+ */
+fn site_files(env: &Arc<Env>) -> BoxedFilter<(Routed,)> {
+    let mut filter = warp::any()
+        .and_then(|| async { Err::<Routed, _>(warp::reject::not_found()) })
+        .boxed();
+    for site in &env.sites {
+        let is_site = {
+            let env = env.clone();
+            let site = site.clone();
+            warp::header::optional::<String>("host")
+                .and_then(move |host_opt: Option<String>| {
+                    let selected = env.site(host_opt.as_deref());
+                    let site = site.clone();
+                    async move {
+                        match selected {
+                            Some(selected) if Arc::ptr_eq(&selected, &site) => Ok(()),
+                            _ => Err(warp::reject::not_found()),
+                        }
+                    }
+                })
+                .untuple_one()
+        };
+
+        let with_site = {
+            let env = env.clone();
+            let site = site.clone();
+            move || (env.clone(), site.clone())
+        };
+
+        let top = warp::get()
+            .and(warp::path::end())
+            .map(with_site.clone())
+            .untuple_one()
+            .and(warp::header::optional("accept-encoding"))
+            .and(warp::header::optional("if-none-match"))
+            .and(warp::fs::file(site.resource_path.index_html_path.clone()))
+            .and_then(handler::static_file::handle)
+            .map(|reply| Routed::new("top", reply));
+
+        let static_resource = warp::path("static")
+            .map(with_site)
+            .untuple_one()
+            .and(warp::header::optional("accept-encoding"))
+            .and(warp::header::optional("if-none-match"))
+            .and(warp::fs::dir(site.resource_path.static_root_dir.clone()))
+            .and_then(handler::static_file::handle)
+            .map(|reply| Routed::new("static", reply));
+
+        filter = filter
+            .or(is_site.and(top.or(static_resource).unify()))
+            .unify()
+            .boxed();
+    }
+    filter
+}
+
+/**
+ * Pass requests only for hosts of expose URLs.
+ */
+fn on_expose_host(env: &Arc<Env>) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    let env = env.clone();
+    warp::header::optional::<String>("host")
+        .and_then(move |host_opt: Option<String>| {
+            let is_expose_host = host_opt.is_some_and(|host| env.is_expose_host(&host));
+            async move {
+                if is_expose_host {
+                    Ok(())
+                } else {
+                    Err(warp::reject::not_found())
+                }
+            }
+        })
+        .untuple_one()
+}
+
+/**
+ * Apply CORS to responses, and log accesses.
+ */
+fn with_access_log(
+    env: Arc<Env>,
+    cors: Arc<CorsOptions>,
+    routes: BoxedFilter<(Routed,)>,
+) -> BoxedFilter<(Response,)> {
+    warp::any()
+        .map(Instant::now)
+        .and(warp::method())
+        .and(warp::header::optional::<String>("host"))
+        .and(warp::filters::path::full())
+        .and(warp::header::optional::<String>("origin"))
+        .and(routes)
+        .map(
+            move |started_at, method, host, path, origin: Option<String>, routed: Routed| {
+                let route = routed.route;
+                let mut response = routed.reply.into_response();
+                apply_cors(&cors, origin.as_deref(), &mut response);
+                log_access(&env, started_at, method, host, path, route, &response);
+                response
+            },
+        )
+        .boxed()
+}
+
+fn enabled(flag: bool) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::any()
+        .and_then(move || async move {
+            if flag {
+                Ok(())
+            } else {
+                Err(warp::reject::not_found())
+            }
+        })
+        .untuple_one()
+}
+
+fn apply_cors(cors: &CorsOptions, origin_opt: Option<&str>, response: &mut Response) {
+    let headers = response.headers_mut();
+    if cors.allow_origins.iter().any(|allowed| allowed == "*") {
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+        return;
+    }
+
+    headers.append(VARY, HeaderValue::from_static("Origin"));
+    let allowed_origin =
+        origin_opt.filter(|origin| cors.allow_origins.iter().any(|allowed| allowed == origin));
+    if let Some(origin) = allowed_origin {
+        if let Ok(value) = HeaderValue::from_str(origin) {
+            headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, value);
+        }
+    }
+}
+
+/**
+ * Log an access, and count it for metrics.
+ */
+fn log_access(
+    env: &Env,
+    started_at: Instant,
+    method: Method,
+    host: Option<String>,
+    path: FullPath,
+    route: &'static str,
+    response: &Response,
+) {
+    let status = response.status().as_u16();
+    let elapsed = started_at.elapsed();
+    env.metrics.record_request(route, status, elapsed);
+    info!(
+        target: "archivedon::access",
+        "method={}, host={}, path={}, status={}, route={}, latency={:?}",
+        method,
+        host.as_deref().unwrap_or("-"),
+        path.as_str(),
+        status,
+        route,
+        elapsed,
+    );
+}
+
+/**
+ * Run the server until SIGTERM or SIGINT, and then wait in-flight requests until the drain timeout.
+ */
+async fn serve_until_shutdown(
+    server: impl Future<Output = ()>,
+    env: Arc<Env>,
+    shutdown: watch::Sender<()>,
+    drain_timeout: Duration,
+) -> Result<(), Box<dyn Error>> {
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut sigint = signal(SignalKind::interrupt())?;
+
+    tokio::pin!(server);
+    tokio::select! {
+        _ = &mut server => return Ok(()),
+        _ = sigterm.recv() => info!("Received SIGTERM, shutting down."),
+        _ = sigint.recv() => info!("Received SIGINT, shutting down."),
+    }
+
+    env.start_shutdown();
+    let _ = shutdown.send(());
+    if tokio::time::timeout(drain_timeout, server).await.is_err() {
+        warn!("Timed out to drain in-flight requests: timeout={drain_timeout:?}");
+    }
+    Ok(())
+}
+
+/**
+ * Reload the archive and certificates on SIGHUP, or when they are updated.
+ */
+fn spawn_reloader(
+    env: Arc<Env>,
+    cert_resolver: Option<Arc<CertResolver>>,
+    reload_interval: Option<Duration>,
+) -> Result<(), Box<dyn Error>> {
+    let mut sighup = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        let mut interval = reload_interval.map(tokio::time::interval);
+        loop {
+            let by_signal = match &mut interval {
+                None => {
+                    sighup.recv().await;
+                    true
+                }
+                Some(interval) => {
+                    tokio::select! {
+                        _ = sighup.recv() => true,
+                        _ = interval.tick() => false,
+                    }
+                }
+            };
 
-    let (_, server) = warp::serve(service).try_bind_ephemeral(sock_addr)?;
-    server.await;
+            for site in &env.sites {
+                if by_signal || site.is_updated() {
+                    let site = site.clone();
+                    if let Err(err) = tokio::task::spawn_blocking(move || site.reload()).await {
+                        error!("Failed to reload resources: err={err}");
+                    }
+                }
+            }
 
+            if let Some(cert_resolver) = &cert_resolver {
+                if by_signal || cert_resolver.is_updated() {
+                    cert_resolver.reload();
+                }
+            }
+        }
+    });
     Ok(())
 }
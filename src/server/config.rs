@@ -0,0 +1,355 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::Deserialize;
+use url::Url;
+use warp::http::StatusCode;
+
+use crate::server::cmd::serve::{CorsOptions, Options};
+use crate::server::env::{site::SiteOptions, CacheOptions, HostOptions, NodeMetadata};
+use crate::server::tls::CertificateFile;
+
+const DEFAULT_DRAIN_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_INDEX_MAX_ENTRIES: usize = 100_000;
+const REDIRECT_STATUSES: [u16; 5] = [301, 302, 303, 307, 308];
+
+/**
+ * A configuration file of `archivedon serve`, in TOML or YAML. Options of the command line override it.
+ */
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub addrs: Vec<String>,
+    pub port: Option<u16>,
+    pub resource_dir: Option<PathBuf>,
+    pub expose_url_base: Option<String>,
+    pub gone_top_link: Option<bool>,
+    pub index: Option<bool>,
+    /// Files indexed at most, and the rest are read from the disk. Requires `index`.
+    pub index_max_entries: Option<usize>,
+    pub reload_interval_secs: Option<u64>,
+    pub drain_timeout_secs: Option<u64>,
+    pub metrics: Option<bool>,
+    /// An address like `127.0.0.1:9090` to serve health checks and metrics separately.
+    pub admin_addr: Option<String>,
+    #[serde(default)]
+    pub tls: Vec<TlsConfig>,
+    #[serde(default)]
+    pub node: NodeConfig,
+    #[serde(default)]
+    pub cors: CorsConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    /// Behavior per host, keyed by host names of requests.
+    #[serde(default)]
+    pub hosts: BTreeMap<String, HostConfig>,
+    /// Archives selected by hosts of requests. The top level archive serves other hosts if given.
+    #[serde(default)]
+    pub sites: Vec<SiteConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SiteConfig {
+    /// Hosts served by this site such as old domains, in addition to the host of `expose_url_base`.
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    pub resource_dir: PathBuf,
+    pub expose_url_base: String,
+    pub gone_top_link: Option<bool>,
+    /// NodeInfo metadata, which defaults to the top level one per field.
+    #[serde(default)]
+    pub node: NodeConfig,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct NodeConfig {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub maintainer_name: Option<String>,
+    pub maintainer_email: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Origins allowed by `Access-Control-Allow-Origin`, which allows any origin by default.
+    pub allow_origins: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct CacheConfig {
+    pub static_max_age_secs: Option<u64>,
+    /// Defaults to `static_max_age_secs`.
+    pub static_json_max_age_secs: Option<u64>,
+    /// Defaults to `static_max_age_secs`, which is also used for top pages.
+    pub html_max_age_secs: Option<u64>,
+    pub webfinger_max_age_secs: Option<u64>,
+    pub redirect_max_age_secs: Option<u64>,
+    pub gone_max_age_secs: Option<u64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct HostConfig {
+    pub gone_top_link: Option<bool>,
+    pub redirect_status: Option<u16>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(x) => x,
+            Err(err) => {
+                return Err(format!(
+                    "Failed to read config file: path={}, err={err}",
+                    path.display()
+                )
+                .into())
+            }
+        };
+        let result = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content).map_err(|err| err.to_string()),
+            Some("yaml") | Some("yml") => {
+                serde_yaml::from_str(&content).map_err(|err| err.to_string())
+            }
+            _ => {
+                return Err(format!(
+                    "Unknown config file format, expected .toml, .yaml or .yml: path={}",
+                    path.display()
+                )
+                .into())
+            }
+        };
+        match result {
+            Ok(x) => Ok(x),
+            Err(err) => {
+                Err(format!("Invalid config file: path={}, err={err}", path.display()).into())
+            }
+        }
+    }
+
+    /**
+     * Validate the configuration, and convert it into options of the server.
+     */
+    pub fn into_options(self) -> Result<Options, Box<dyn Error>> {
+        let mut addrs = vec![];
+        for addr in &self.addrs {
+            match addr.parse::<IpAddr>() {
+                Ok(x) => addrs.push(x),
+                Err(err) => return Err(format!("Invalid addrs: value={addr}, err={err}").into()),
+            }
+        }
+        if addrs.is_empty() {
+            addrs.push(IpAddr::from([0, 0, 0, 0]));
+        }
+
+        let port = match self.port {
+            Some(x) => x,
+            None => return Err(required_error("port", "--port", "PORT")),
+        };
+
+        let gone_top_link = self.gone_top_link.unwrap_or(false);
+        let default_site = match (self.resource_dir, &self.expose_url_base) {
+            (None, None) if !self.sites.is_empty() => None,
+            (None, _) => {
+                return Err(required_error(
+                    "resource_dir",
+                    "--resource-dir",
+                    "RESOURCE_DIR",
+                ))
+            }
+            (_, None) => {
+                return Err(required_error(
+                    "expose_url_base",
+                    "--expose-url-base",
+                    "EXPOSE_URL_BASE",
+                ))
+            }
+            (Some(resource_dir), Some(expose_url_base)) => Some(SiteOptions {
+                hosts: vec![],
+                resource_dir,
+                expose_url_base: validate_expose_url_base(expose_url_base)?,
+                gone_top_link,
+                node_metadata: self.node.clone().into_metadata(),
+            }),
+        };
+
+        let mut sites = vec![];
+        for site in self.sites {
+            let node = NodeConfig {
+                name: site.node.name.or(self.node.name.clone()),
+                description: site.node.description.or(self.node.description.clone()),
+                maintainer_name: site
+                    .node
+                    .maintainer_name
+                    .or(self.node.maintainer_name.clone()),
+                maintainer_email: site
+                    .node
+                    .maintainer_email
+                    .or(self.node.maintainer_email.clone()),
+            };
+            sites.push(SiteOptions {
+                hosts: site.hosts,
+                resource_dir: site.resource_dir,
+                expose_url_base: validate_expose_url_base(&site.expose_url_base)?,
+                gone_top_link: site.gone_top_link.unwrap_or(gone_top_link),
+                node_metadata: node.into_metadata(),
+            });
+        }
+
+        let reload_interval = match self.reload_interval_secs {
+            None => None,
+            Some(0) => return Err("reload_interval_secs must be positive.".into()),
+            Some(x) => Some(Duration::from_secs(x)),
+        };
+
+        let index_max_entries = match (self.index, self.index_max_entries) {
+            (_, Some(0)) => return Err("index_max_entries must be positive.".into()),
+            (Some(true), max_entries) => Some(max_entries.unwrap_or(DEFAULT_INDEX_MAX_ENTRIES)),
+            (_, Some(_)) => return Err("index_max_entries requires index = true.".into()),
+            (_, None) => None,
+        };
+
+        let admin_addr = match &self.admin_addr {
+            None => None,
+            Some(addr) => match addr.parse::<SocketAddr>() {
+                Ok(x) => Some(x),
+                Err(err) => {
+                    return Err(format!("Invalid admin_addr: value={addr}, err={err}").into())
+                }
+            },
+        };
+
+        let cors = match self.cors.allow_origins {
+            None => CorsOptions {
+                allow_origins: vec!["*".to_string()],
+            },
+            Some(allow_origins) => {
+                for origin in &allow_origins {
+                    validate_origin(origin)?;
+                }
+                CorsOptions { allow_origins }
+            }
+        };
+
+        let mut hosts = HashMap::new();
+        for (host, host_config) in self.hosts {
+            let redirect_status = match host_config.redirect_status {
+                None => StatusCode::MOVED_PERMANENTLY,
+                Some(x) if REDIRECT_STATUSES.contains(&x) => StatusCode::from_u16(x)?,
+                Some(x) => {
+                    return Err(format!(
+                        "Invalid redirect_status, expected one of {REDIRECT_STATUSES:?}: host={host}, value={x}"
+                    )
+                    .into())
+                }
+            };
+            hosts.insert(
+                host.to_lowercase(),
+                HostOptions {
+                    gone_top_link: host_config.gone_top_link,
+                    redirect_status,
+                },
+            );
+        }
+
+        Ok(Options {
+            addrs,
+            port,
+            default_site,
+            sites,
+            index_max_entries,
+            reload_interval,
+            tls_certificates: self
+                .tls
+                .into_iter()
+                .map(|tls| CertificateFile {
+                    cert_path: tls.cert,
+                    key_path: tls.key,
+                })
+                .collect(),
+            drain_timeout: Duration::from_secs(
+                self.drain_timeout_secs
+                    .unwrap_or(DEFAULT_DRAIN_TIMEOUT_SECS),
+            ),
+            metrics: self.metrics.unwrap_or(false),
+            admin_addr,
+            cors,
+            cache: CacheOptions {
+                static_max_age: self.cache.static_max_age_secs.map(Duration::from_secs),
+                static_json_max_age: self
+                    .cache
+                    .static_json_max_age_secs
+                    .or(self.cache.static_max_age_secs)
+                    .map(Duration::from_secs),
+                html_max_age: self
+                    .cache
+                    .html_max_age_secs
+                    .or(self.cache.static_max_age_secs)
+                    .map(Duration::from_secs),
+                webfinger_max_age: self.cache.webfinger_max_age_secs.map(Duration::from_secs),
+                redirect_max_age: self.cache.redirect_max_age_secs.map(Duration::from_secs),
+                gone_max_age: self.cache.gone_max_age_secs.map(Duration::from_secs),
+            },
+            hosts,
+        })
+    }
+}
+
+impl NodeConfig {
+    fn into_metadata(self) -> NodeMetadata {
+        NodeMetadata {
+            node_name: self.name,
+            node_description: self.description,
+            maintainer_name: self.maintainer_name,
+            maintainer_email: self.maintainer_email,
+        }
+    }
+}
+
+fn required_error(key: &str, flag: &str, env: &str) -> Box<dyn Error> {
+    format!("{key} is required: give {flag}, {env} or `{key}` in the config file.").into()
+}
+
+fn validate_expose_url_base(value: &str) -> Result<Url, Box<dyn Error>> {
+    let url = match Url::parse(value) {
+        Ok(x) => x,
+        Err(err) => return Err(format!("Invalid expose_url_base: value={value}, err={err}").into()),
+    };
+    if !matches!(url.scheme(), "http" | "https") || url.host().is_none() {
+        return Err(format!(
+            "expose_url_base must be a HTTP or HTTPS URL with a host: value={value}"
+        )
+        .into());
+    }
+    Ok(url)
+}
+
+fn validate_origin(value: &str) -> Result<(), Box<dyn Error>> {
+    if value == "*" {
+        return Ok(());
+    }
+    match Url::parse(value) {
+        Ok(url) if url.origin().ascii_serialization() == value => Ok(()),
+        _ => Err(format!(
+            "Invalid cors.allow_origins, expected * or an origin such as https://example.com: value={value}"
+        )
+        .into()),
+    }
+}
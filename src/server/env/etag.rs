@@ -0,0 +1,78 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+
+use crate::entity_tag;
+
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+const MAX_CACHED_ETAGS: usize = 10_000;
+
+/**
+ * Entity tags of files, which are computed again only if files are modified.
+ *
+ * Tags are cached up to `MAX_CACHED_ETAGS` files, and all of them are dropped when it is full.
+ */
+#[derive(Default)]
+pub struct FileETags {
+    cache: Mutex<HashMap<PathBuf, CachedETag>>,
+}
+
+struct CachedETag {
+    modified: SystemTime,
+    len: u64,
+    etag: String,
+}
+
+impl FileETags {
+    pub async fn get(&self, path: &Path) -> Option<String> {
+        let metadata = tokio::fs::metadata(path).await.ok()?;
+        let modified = metadata.modified().ok()?;
+        if let Some(cached) = self.lock().get(path) {
+            if cached.modified == modified && cached.len == metadata.len() {
+                return Some(cached.etag.clone());
+            }
+        }
+
+        // Hashed from a stream, since files such as media may be large.
+        let mut file = tokio::fs::File::open(path).await.ok()?;
+        let mut digest = Sha256::new();
+        let mut buf = vec![0; HASH_BUFFER_SIZE];
+        loop {
+            match file.read(&mut buf).await.ok()? {
+                0 => break,
+                len => digest.update(&buf[..len]),
+            }
+        }
+        let etag = entity_tag::from_digest(digest);
+        let mut cache = self.lock();
+        if cache.len() >= MAX_CACHED_ETAGS {
+            cache.clear();
+        }
+        cache.insert(
+            path.to_path_buf(),
+            CachedETag {
+                modified,
+                len: metadata.len(),
+                etag: etag.clone(),
+            },
+        );
+        Some(etag)
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, CachedETag>> {
+        match self.cache.lock() {
+            Ok(cache) => cache,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
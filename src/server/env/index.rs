@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::manifest::Manifest;
+use crate::redirect_map::RedirectMap;
+use crate::resource_path::ResourcePath;
+use crate::webfinger::alias::Alias as WebfingerAlias;
+use crate::webfinger::resource::Resource as WebfingerResource;
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::de::DeserializeOwned;
+
+/**
+ * A snapshot of the archive, which is replaced on reload.
+ */
+pub struct ResourceIndex {
+    /// When the archive is built, which is used as the time of deletion.
+    pub archived_at: Option<DateTime<Utc>>,
+    pub manifest: Option<Manifest>,
+    pub manifest_modified: Option<SystemTime>,
+    /// Resources keyed by file paths, which are available only if indexing is enabled.
+    pub files: Option<IndexedFiles>,
+}
+
+/**
+ * A result of looking up the index.
+ */
+pub enum Indexed<'a, T> {
+    Found(&'a T),
+    /// The file does not exist, since all files are indexed.
+    Missing,
+    /// The file should be read from the disk.
+    NotIndexed,
+}
+
+pub struct IndexedFiles {
+    pub redirect_maps: HashMap<PathBuf, RedirectMap>,
+    pub webfinger_resources: HashMap<PathBuf, WebfingerResource>,
+    pub webfinger_aliases: HashMap<PathBuf, WebfingerAlias>,
+    /// Whether all files are indexed. Otherwise, files not in the index should be read from the disk.
+    pub complete: bool,
+}
+
+impl ResourceIndex {
+    /**
+     * Load the archive, indexing up to `max_entries` files if given.
+     */
+    pub fn load(resource_path: &ResourcePath, max_entries: Option<usize>) -> Self {
+        let archived_at = modified(&resource_path.index_html_path).map(DateTime::<Utc>::from);
+        let manifest_modified = modified(&resource_path.manifest_path);
+        let manifest = load_json_file(&resource_path.manifest_path);
+
+        let files = max_entries.map(|max_entries| {
+            let mut files = IndexedFiles {
+                redirect_maps: HashMap::new(),
+                webfinger_resources: HashMap::new(),
+                webfinger_aliases: HashMap::new(),
+                complete: false,
+            };
+            let mut remaining = max_entries;
+            files.complete = load_json_files(
+                &resource_path.redirect_map_root_dir,
+                &mut files.redirect_maps,
+                &mut remaining,
+            ) && load_json_files(
+                &resource_path.webfinger_root_dir,
+                &mut files.webfinger_resources,
+                &mut remaining,
+            ) && load_json_files(
+                &resource_path.webfinger_alias_root_dir,
+                &mut files.webfinger_aliases,
+                &mut remaining,
+            );
+            if !files.complete {
+                warn!(
+                    "Some files are not indexed, and read from the disk: max_entries={max_entries}"
+                );
+            }
+            files
+        });
+
+        Self {
+            archived_at,
+            manifest,
+            manifest_modified,
+            files,
+        }
+    }
+
+    pub fn indexed<'a, T>(
+        &'a self,
+        select: impl FnOnce(&'a IndexedFiles) -> &'a HashMap<PathBuf, T>,
+        path: &Path,
+    ) -> Indexed<'a, T> {
+        let files = match &self.files {
+            None => return Indexed::NotIndexed,
+            Some(x) => x,
+        };
+        match select(files).get(path) {
+            Some(x) => Indexed::Found(x),
+            None if files.complete => Indexed::Missing,
+            None => Indexed::NotIndexed,
+        }
+    }
+}
+
+pub fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn load_json_file<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let content = match std::fs::read(path) {
+        Ok(x) => x,
+        Err(err) => {
+            warn!("Failed to read file: path={}, err={}", path.display(), err);
+            return None;
+        }
+    };
+    match serde_json::from_slice(&content) {
+        Ok(x) => Some(x),
+        Err(err) => {
+            warn!(
+                "Failed to deserialize file: path={}, err={}",
+                path.display(),
+                err
+            );
+            None
+        }
+    }
+}
+
+/**
+ * Load JSON files into `files` up to `remaining`, and return whether all files are loaded.
+ */
+fn load_json_files<T: DeserializeOwned>(
+    root_dir: &Path,
+    files: &mut HashMap<PathBuf, T>,
+    remaining: &mut usize,
+) -> bool {
+    match walk_json_files(root_dir, files, remaining) {
+        Ok(complete) => complete,
+        Err(err) => {
+            warn!(
+                "Failed to index directory: path={}, err={}",
+                root_dir.display(),
+                err
+            );
+            false
+        }
+    }
+}
+
+fn walk_json_files<T: DeserializeOwned>(
+    dir: &Path,
+    files: &mut HashMap<PathBuf, T>,
+    remaining: &mut usize,
+) -> Result<bool, Box<dyn Error>> {
+    if !dir.exists() {
+        return Ok(true);
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if !walk_json_files(&path, files, remaining)? {
+                return Ok(false);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            if *remaining == 0 {
+                return Ok(false);
+            }
+            if let Some(content) = load_json_file(&path) {
+                files.insert(path, content);
+                *remaining -= 1;
+            }
+        }
+    }
+    Ok(true)
+}
@@ -1,8 +1,21 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
-use archivedon::resource_path::ResourcePath;
-use url::Url;
+use warp::http::StatusCode;
 
+use self::site::{Site, SiteOptions};
+use crate::server::metrics::Metrics;
+
+pub mod etag;
+pub mod index;
+pub mod site;
 pub mod user;
 
 pub const PROG_NAME: &str = env!("CARGO_PKG_NAME");
@@ -11,15 +24,158 @@ pub const PROG_REPOSITORY: Option<&str> = option_env!("CARGO_PKG_REPOSITORY");
 pub const PROG_HOMEPAGE: Option<&str> = option_env!("CARGO_PKG_HOMEPAGE");
 
 pub struct Env {
-    pub resource_path: ResourcePath,
-    pub expose_url_base: Url,
+    pub sites: Vec<Arc<Site>>,
+    site_by_host: HashMap<String, Arc<Site>>,
+    default_site: Option<Arc<Site>>,
+    hosts: HashMap<String, HostOptions>,
+    pub cache: CacheOptions,
+    shutting_down: AtomicBool,
+    pub metrics: Metrics,
+}
+
+/**
+ * Metadata of the node for NodeInfo.
+ */
+pub struct NodeMetadata {
+    pub node_name: Option<String>,
+    pub node_description: Option<String>,
+    pub maintainer_name: Option<String>,
+    pub maintainer_email: Option<String>,
+}
+
+/**
+ * Behavior for requests to a specific host.
+ */
+pub struct HostOptions {
+    pub gone_top_link: Option<bool>,
+    pub redirect_status: StatusCode,
+}
+
+/**
+ * `max-age` of `Cache-Control` per class of responses. No `Cache-Control` is given if unset.
+ */
+#[derive(Default)]
+pub struct CacheOptions {
+    /// Static files other than JSON and HTML, such as media.
+    pub static_max_age: Option<Duration>,
+    pub static_json_max_age: Option<Duration>,
+    pub html_max_age: Option<Duration>,
+    pub webfinger_max_age: Option<Duration>,
+    pub redirect_max_age: Option<Duration>,
+    pub gone_max_age: Option<Duration>,
 }
 
 impl Env {
-    pub fn load(resource_dir: &Path, expose_url_base: Url) -> Arc<Env> {
-        Arc::new(Env {
-            resource_path: ResourcePath::new(resource_dir.to_path_buf()),
-            expose_url_base,
-        })
+    /**
+     * Load sites. The default site serves requests to hosts which no site is configured for.
+     */
+    pub fn load(
+        default_site: Option<SiteOptions>,
+        sites: Vec<SiteOptions>,
+        hosts: HashMap<String, HostOptions>,
+        cache: CacheOptions,
+        index_max_entries: Option<usize>,
+    ) -> Result<Arc<Env>, Box<dyn Error>> {
+        let default_site =
+            default_site.map(|options| Arc::new(Site::load(options, index_max_entries)));
+
+        let mut loaded_sites = vec![];
+        let mut site_by_host = HashMap::new();
+        for options in sites {
+            let mut site_hosts = options.hosts.clone();
+            if let Some(host) = options.expose_url_base.host_str() {
+                site_hosts.push(host.to_string());
+            }
+            let site = Arc::new(Site::load(options, index_max_entries));
+            for host in site_hosts {
+                let host = host_name(&host);
+                let duplicated = match site_by_host.get(&host) {
+                    None => false,
+                    Some(other) => !Arc::ptr_eq(other, &site),
+                };
+                if duplicated {
+                    return Err(
+                        format!("A host is configured for multiple sites: host={host}").into(),
+                    );
+                }
+                site_by_host.insert(host, site.clone());
+            }
+            loaded_sites.push(site);
+        }
+        if let Some(site) = &default_site {
+            loaded_sites.push(site.clone());
+        }
+
+        Ok(Arc::new(Env {
+            sites: loaded_sites,
+            site_by_host,
+            default_site,
+            hosts,
+            cache,
+            shutting_down: AtomicBool::new(false),
+            metrics: Metrics::default(),
+        }))
+    }
+
+    /**
+     * Select a site by the Host header.
+     */
+    pub fn site(&self, host_opt: Option<&str>) -> Option<Arc<Site>> {
+        host_opt
+            .and_then(|host| self.site_by_host.get(&host_name(host)))
+            .or(self.default_site.as_ref())
+            .cloned()
+    }
+
+    /**
+     * A host name of the Host header if it is configured, which is safe to build URLs from.
+     */
+    pub fn configured_host(&self, host: &str) -> Option<String> {
+        let name = host_name(host);
+        if self.site_by_host.contains_key(&name) || self.hosts.contains_key(&name) {
+            Some(name)
+        } else {
+            None
+        }
     }
+
+    pub fn is_expose_host(&self, host: &str) -> bool {
+        let name = host_name(host);
+        self.sites
+            .iter()
+            .any(|site| site.expose_url_base.host_str() == Some(name.as_str()))
+    }
+
+    pub fn gone_top_link(&self, site: &Site, host_opt: Option<&str>) -> bool {
+        host_opt
+            .and_then(|host| self.hosts.get(&host_name(host)))
+            .and_then(|options| options.gone_top_link)
+            .unwrap_or(site.gone_top_link)
+    }
+
+    pub fn redirect_status(&self, host: &str) -> StatusCode {
+        match self.hosts.get(&host_name(host)) {
+            Some(options) => options.redirect_status,
+            None => StatusCode::MOVED_PERMANENTLY,
+        }
+    }
+
+    pub fn start_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::Relaxed)
+    }
+}
+
+/**
+ * A lowercased host name without the port.
+ */
+fn host_name(host: &str) -> String {
+    let name = match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    };
+    name.to_lowercase()
 }
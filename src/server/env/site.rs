@@ -0,0 +1,86 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use crate::resource_path::ResourcePath;
+use log::info;
+use url::Url;
+
+use super::etag::FileETags;
+use super::index::{self, ResourceIndex};
+use super::NodeMetadata;
+
+/**
+ * An archive served for some hosts, with its own resource directory and expose URL.
+ */
+pub struct Site {
+    pub resource_path: ResourcePath,
+    pub expose_url_base: Url,
+    pub gone_top_link: bool,
+    pub node_metadata: NodeMetadata,
+    pub etags: FileETags,
+    /// Index up to this number of files if given.
+    index_max_entries: Option<usize>,
+    index: RwLock<Arc<ResourceIndex>>,
+}
+
+pub struct SiteOptions {
+    /// Hosts of requests for this site, in addition to the host of the expose URL.
+    pub hosts: Vec<String>,
+    pub resource_dir: PathBuf,
+    pub expose_url_base: Url,
+    pub gone_top_link: bool,
+    pub node_metadata: NodeMetadata,
+}
+
+impl Site {
+    pub fn load(options: SiteOptions, index_max_entries: Option<usize>) -> Self {
+        let resource_path = ResourcePath::new(options.resource_dir);
+        let index = ResourceIndex::load(&resource_path, index_max_entries);
+
+        Self {
+            resource_path,
+            expose_url_base: options.expose_url_base,
+            gone_top_link: options.gone_top_link,
+            node_metadata: options.node_metadata,
+            etags: FileETags::default(),
+            index_max_entries,
+            index: RwLock::new(Arc::new(index)),
+        }
+    }
+
+    pub fn index(&self) -> Arc<ResourceIndex> {
+        match self.index.read() {
+            Ok(index) => index.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /**
+     * Reload the archive, which blocks while reading files.
+     */
+    pub fn reload(&self) {
+        let index = Arc::new(ResourceIndex::load(
+            &self.resource_path,
+            self.index_max_entries,
+        ));
+        match self.index.write() {
+            Ok(mut current) => *current = index,
+            Err(poisoned) => *poisoned.into_inner() = index,
+        }
+        // Removed files are never looked up again.
+        self.etags.clear();
+        info!(
+            "Reloaded resources: path={}",
+            self.resource_path.static_root_dir.display()
+        );
+    }
+
+    /**
+     * Whether the archive is updated since the last reload, which is detected by the manifest.
+     */
+    pub fn is_updated(&self) -> bool {
+        index::modified(&self.resource_path.manifest_path) != self.index().manifest_modified
+    }
+}
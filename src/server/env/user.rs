@@ -1,3 +1,4 @@
+#[allow(dead_code)]
 pub trait UserData {
     fn get(name: String) -> Option<String>;
 }
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use crate::content_negotiation::accept::Accept;
+use chrono::SecondsFormat;
+use serde_json::json;
+use warp::filters::path::FullPath;
+
+use crate::server::env::Env;
+use crate::server::handler;
+
+const ACTIVITY_JSON: &str = "application/activity+json";
+const TEXT_HTML: &str = "text/html";
+
+/**
+ * Gone responses negotiated by the Accept header:
+ * a Tombstone for ActivityPub clients, a HTML page for browsers and a plain text for others.
+ */
+pub async fn handle(
+    env: Arc<Env>,
+    host_opt: Option<String>,
+    path: FullPath,
+    accept: Option<String>,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let negotiated_type_opt = match &accept {
+        None => None,
+        Some(accept) => Accept::parse(accept).negotiate_explicitly(&[ACTIVITY_JSON, TEXT_HTML]),
+    };
+
+    let site = match env.site(host_opt.as_deref()) {
+        None => return Ok(handler::not_found()),
+        Some(x) => x,
+    };
+
+    let reply = match negotiated_type_opt {
+        Some(ACTIVITY_JSON) => {
+            // The ID is built from the Host header only if it is configured, since clients may give any.
+            let mut base_url = site.expose_url_base.clone();
+            if let Some(host) = host_opt.as_deref().and_then(|x| env.configured_host(x)) {
+                if base_url.host_str() != Some(host.as_str())
+                    && (base_url.set_host(Some(&host)).is_err() || base_url.set_port(None).is_err())
+                {
+                    return Ok(handler::bad_request());
+                }
+            }
+            let id = match base_url.join(path.as_str()) {
+                Ok(x) => x.to_string(),
+                Err(_) => return Ok(handler::bad_request()),
+            };
+            let mut tombstone = json!({
+                "@context": "https://www.w3.org/ns/activitystreams",
+                "id": id,
+                "type": "Tombstone",
+                "formerType": "Object",
+            });
+            if let Some(archived_at) = &site.index().archived_at {
+                tombstone["deleted"] =
+                    json!(archived_at.to_rfc3339_opts(SecondsFormat::Secs, true));
+            }
+
+            let reply = warp::reply::json(&tombstone);
+            let reply = warp::reply::with_header(reply, "Content-Type", ACTIVITY_JSON);
+            Box::new(reply) as Box<dyn warp::Reply>
+        }
+        Some(_) => {
+            let top_link = if env.gone_top_link(&site, host_opt.as_deref()) {
+                format!(
+                    "<p><a href=\"{}\">Go to the top page of the archive.</a></p>\n",
+                    handlebars::html_escape(site.expose_url_base.as_str())
+                )
+            } else {
+                String::new()
+            };
+            let html = format!(
+                "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Gone</title></head>\n<body>\n<h1>Gone</h1>\n<p>This resource is no longer available.</p>\n{top_link}</body>\n</html>\n"
+            );
+            Box::new(warp::reply::html(html))
+        }
+        None => handler::gone(),
+    };
+
+    // No validators are given as for redirects, since preconditions are ignored for 410.
+    let reply = warp::reply::with_status(reply, warp::http::StatusCode::GONE);
+    let reply = warp::reply::with_header(reply, "Vary", "Accept");
+
+    Ok(handler::with_cache_control(
+        Box::new(reply),
+        env.cache.gone_max_age,
+    ))
+}
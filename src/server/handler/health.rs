@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use log::warn;
+
+use crate::server::env::Env;
+
+pub async fn handle_healthz() -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    Ok(Box::new(warp::reply::with_status(
+        "OK",
+        warp::http::StatusCode::OK,
+    )))
+}
+
+/**
+ * Ready if resource directories of all sites can be read, and the server is not shutting down.
+ */
+pub async fn handle_readyz(env: Arc<Env>) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    if env.is_shutting_down() {
+        return Ok(service_unavailable());
+    }
+
+    for site in &env.sites {
+        let static_dir = &site.resource_path.static_root_dir;
+        if let Err(err) = tokio::fs::read_dir(static_dir).await {
+            warn!(
+                "Resource directory is not readable: path={}, err={}",
+                static_dir.display(),
+                err
+            );
+            return Ok(service_unavailable());
+        }
+    }
+
+    Ok(Box::new(warp::reply::with_status(
+        "OK",
+        warp::http::StatusCode::OK,
+    )))
+}
+
+fn service_unavailable() -> Box<dyn warp::Reply> {
+    Box::new(warp::reply::with_status(
+        "Service Unavailable",
+        warp::http::StatusCode::SERVICE_UNAVAILABLE,
+    ))
+}
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use crate::host_meta::HostMeta;
+
+use crate::server::env::Env;
+use crate::server::handler;
+
+pub async fn handle_xrd(
+    env: Arc<Env>,
+    host_opt: Option<String>,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let host_meta = match host_meta_for(&env, host_opt) {
+        None => return Ok(handler::not_found()),
+        Some(x) => x,
+    };
+
+    let reply = warp::reply::with_header(
+        host_meta.to_xrd(),
+        "Content-Type",
+        "application/xrd+xml; charset=utf-8",
+    );
+
+    Ok(Box::new(reply))
+}
+
+pub async fn handle_json(
+    env: Arc<Env>,
+    host_opt: Option<String>,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let host_meta = match host_meta_for(&env, host_opt) {
+        None => return Ok(handler::not_found()),
+        Some(x) => x,
+    };
+
+    let reply = warp::reply::json(&host_meta);
+
+    Ok(Box::new(reply))
+}
+
+/**
+ * Point to WebFinger on the requested host, since any archived domain may be routed here.
+ */
+fn host_meta_for(env: &Env, host_opt: Option<String>) -> Option<HostMeta> {
+    let site = env.site(host_opt.as_deref())?;
+    let expose_url_base = &site.expose_url_base;
+    let host = match host_opt {
+        Some(host) => host,
+        None => match expose_url_base.port() {
+            None => expose_url_base.host_str().unwrap_or_default().to_string(),
+            Some(port) => format!("{}:{port}", expose_url_base.host_str().unwrap_or_default()),
+        },
+    };
+    Some(HostMeta::webfinger(expose_url_base.scheme(), &host))
+}
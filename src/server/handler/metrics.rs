@@ -0,0 +1,12 @@
+use std::sync::Arc;
+
+use crate::server::env::Env;
+
+pub async fn handle(env: Arc<Env>) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let reply = warp::reply::with_header(
+        env.metrics.render(),
+        "Content-Type",
+        "text/plain; version=0.0.4",
+    );
+    Ok(Box::new(reply))
+}
@@ -1,7 +1,34 @@
+use std::time::Duration;
+
+use crate::compression::{self, Encoding, Level};
+use log::warn;
+
+pub mod gone;
+pub mod health;
+pub mod host_meta;
+pub mod metrics;
 pub mod nodeinfo;
 pub mod redirect_map;
+pub mod static_file;
 pub mod webfinger;
 
+/**
+ * A reply tagged with the matched route, for access logs and metrics.
+ */
+pub struct Routed {
+    pub route: &'static str,
+    pub reply: Box<dyn warp::Reply>,
+}
+
+impl Routed {
+    pub fn new(route: &'static str, reply: impl warp::Reply + 'static) -> Self {
+        Self {
+            route,
+            reply: Box::new(reply),
+        }
+    }
+}
+
 pub fn bad_request() -> Box<dyn warp::Reply> {
     Box::new(warp::reply::with_status(
         "Bad request",
@@ -29,3 +56,64 @@ pub fn gone() -> Box<dyn warp::Reply> {
         warp::http::StatusCode::GONE,
     ))
 }
+
+pub fn method_not_allowed() -> Box<dyn warp::Reply> {
+    Box::new(warp::reply::with_status(
+        "Method Not Allowed",
+        warp::http::StatusCode::METHOD_NOT_ALLOWED,
+    ))
+}
+
+pub fn not_modified(etag: &str) -> Box<dyn warp::Reply> {
+    let reply =
+        warp::reply::with_status(warp::reply::reply(), warp::http::StatusCode::NOT_MODIFIED);
+    Box::new(warp::reply::with_header(reply, "ETag", etag))
+}
+
+pub fn with_cache_control(
+    reply: Box<dyn warp::Reply>,
+    max_age: Option<Duration>,
+) -> Box<dyn warp::Reply> {
+    match max_age {
+        None => reply,
+        Some(max_age) => Box::new(warp::reply::with_header(
+            reply,
+            "Cache-Control",
+            format!("public, max-age={}", max_age.as_secs()),
+        )),
+    }
+}
+
+/**
+ * Compress the body on the fly by the encoding negotiated with `Accept-Encoding`.
+ */
+pub fn encode_body(body: Vec<u8>, accept_encoding: Option<&str>) -> (Vec<u8>, Option<Encoding>) {
+    let encoding = match accept_encoding.and_then(|x| compression::negotiate(x, &Encoding::ALL)) {
+        None => return (body, None),
+        Some(x) => x,
+    };
+    match encoding.compress(&body, Level::Fast) {
+        Ok(compressed) => (compressed, Some(encoding)),
+        Err(err) => {
+            warn!("Failed to compress a response: encoding={encoding:?}, err={err}");
+            (body, None)
+        }
+    }
+}
+
+pub fn encoded_reply(
+    body: Vec<u8>,
+    content_type: &str,
+    encoding: Option<Encoding>,
+) -> Box<dyn warp::Reply> {
+    let reply = warp::reply::with_header(body, "Content-Type", content_type);
+    let reply = warp::reply::with_header(reply, "Vary", "Accept-Encoding");
+    match encoding {
+        None => Box::new(reply),
+        Some(encoding) => Box::new(warp::reply::with_header(
+            reply,
+            "Content-Encoding",
+            encoding.as_str(),
+        )),
+    }
+}
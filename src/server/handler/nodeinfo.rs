@@ -1,63 +1,147 @@
 use std::sync::Arc;
 
-use archivedon::nodeinfo::{
-    Discovery, DiscoveryItem, MetadataItems, NodeInfo, ServicesItems, SoftwareItems, UsageItems,
-    UsersItems,
+use crate::nodeinfo::{
+    Discovery, DiscoveryItem, MaintainerItems, MetadataItems, NodeInfo, ServicesItems,
+    SoftwareItems, UsageItems, UsersItems, Version,
 };
+use log::error;
 
 use crate::server::env::{self, Env};
+use crate::server::handler;
 
-pub async fn handle_discovery(env: Arc<Env>) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
-    let nodeinfo_url = match env.expose_url_base.join("/archivedon/nodeinfo/2.1.json") {
-        Ok(x) => x,
-        Err(err) => panic!("unreachable: Given URL is valid: {err}"),
+pub async fn handle_discovery(
+    env: Arc<Env>,
+    host_opt: Option<String>,
+    accept_encoding: Option<String>,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let site = match env.site(host_opt.as_deref()) {
+        None => return Ok(handler::not_found()),
+        Some(x) => x,
     };
-
-    let reply = warp::reply::json(&Discovery {
-        links: vec![DiscoveryItem {
-            rel: "http://nodeinfo.diaspora.software/ns/schema/2.1".to_string(),
+    let mut links = vec![];
+    for version in Version::ALL {
+        let nodeinfo_url = match site
+            .expose_url_base
+            .join(&format!("/archivedon/nodeinfo/{}.json", version.as_str()))
+        {
+            Ok(x) => x,
+            Err(err) => {
+                error!(
+                    "Failed to build a NodeInfo URL: base={}, err={}",
+                    site.expose_url_base, err
+                );
+                return Ok(handler::internal_server_error());
+            }
+        };
+        links.push(DiscoveryItem {
+            rel: version.schema_url(),
             href: nodeinfo_url.to_string(),
-        }],
-    });
-    let reply = warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*");
-    let reply = warp::reply::with_header(reply, "Content-Type", "application/jrd+json");
+        });
+    }
+
+    let body = match serde_json::to_vec(&Discovery { links }) {
+        Ok(x) => x,
+        Err(err) => {
+            error!("Failed to serialize NodeInfo discovery: err={err}");
+            return Ok(handler::internal_server_error());
+        }
+    };
+    let (body, encoding) = handler::encode_body(body, accept_encoding.as_deref());
 
-    Ok(Box::new(reply))
+    Ok(handler::encoded_reply(
+        body,
+        "application/jrd+json",
+        encoding,
+    ))
 }
 
-pub async fn handle_resource(_: String) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
-    let reply = warp::reply::json(&NodeInfo {
-        version: "2.1".to_string(),
-        software: SoftwareItems {
-            name: env::PROG_NAME.to_string(),
-            version: env::PROG_VERSION.to_string(),
-            repository: env::PROG_REPOSITORY.map(|x| x.to_string()),
-            homepage: env::PROG_HOMEPAGE.map(|x| x.to_string()),
+pub async fn handle_resource(
+    env: Arc<Env>,
+    host_opt: Option<String>,
+    accept_encoding: Option<String>,
+    file_name: String,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let site = match env.site(host_opt.as_deref()) {
+        None => return Ok(handler::not_found()),
+        Some(x) => x,
+    };
+    let version = match file_name.strip_suffix(".json").and_then(Version::parse) {
+        None => return Ok(handler::not_found()),
+        Some(x) => x,
+    };
+
+    let software = SoftwareItems {
+        name: env::PROG_NAME.to_string(),
+        version: env::PROG_VERSION.to_string(),
+        repository: match version {
+            Version::V2_1 => non_empty(env::PROG_REPOSITORY),
+            _ => None,
         },
-        protocols: vec!["activitypub".to_string()],
-        services: ServicesItems {
-            inbound: vec![],
-            outbound: vec![],
+        homepage: match version {
+            Version::V2_1 => non_empty(env::PROG_HOMEPAGE),
+            _ => None,
         },
-        open_registrations: false,
-        usage: UsageItems {
-            users: UsersItems {
-                total: None,
-                active_halfyear: None,
-                active_month: None,
-            },
-            local_posts: None,
-            local_comments: None,
+    };
+    let services = ServicesItems {
+        inbound: vec![],
+        outbound: vec![],
+    };
+    let index = site.index();
+    let usage = UsageItems {
+        users: UsersItems {
+            total: index.manifest.as_ref().map(|x| x.users_count()),
+            // Archived accounts are never active.
+            active_halfyear: index.manifest.as_ref().map(|_| 0),
+            active_month: index.manifest.as_ref().map(|_| 0),
         },
-        metadata: MetadataItems {
-            node_name: None,
-            node_description: None,
-            maintainer: None,
+        local_posts: index.manifest.as_ref().map(|x| x.posts_count()),
+        local_comments: None,
+    };
+    let node_metadata = &site.node_metadata;
+    let metadata = MetadataItems {
+        node_name: node_metadata.node_name.clone(),
+        node_description: node_metadata.node_description.clone(),
+        maintainer: if node_metadata.maintainer_name.is_none()
+            && node_metadata.maintainer_email.is_none()
+        {
+            None
+        } else {
+            Some(MaintainerItems {
+                name: node_metadata.maintainer_name.clone(),
+                email: node_metadata.maintainer_email.clone(),
+            })
         },
+    };
+
+    let result = serde_json::to_vec(&NodeInfo {
+        version: version.as_str().to_string(),
+        software,
+        protocols: vec!["activitypub".to_string()],
+        services,
+        open_registrations: false,
+        usage,
+        metadata,
     });
-    let reply = warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*");
-    let reply =
-        warp::reply::with_header(reply, "Content-Type", "application/jrd+json; charset=utf-8");
+    let body = match result {
+        Ok(x) => x,
+        Err(err) => {
+            error!(
+                "Failed to serialize NodeInfo: version={}, err={}",
+                version.as_str(),
+                err
+            );
+            return Ok(handler::internal_server_error());
+        }
+    };
+    let (body, encoding) = handler::encode_body(body, accept_encoding.as_deref());
+
+    Ok(handler::encoded_reply(
+        body,
+        &version.content_type(),
+        encoding,
+    ))
+}
 
-    Ok(Box::new(reply))
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value.filter(|x| !x.is_empty()).map(|x| x.to_string())
 }
@@ -1,9 +1,13 @@
+use std::path::Path;
 use std::sync::Arc;
 
-use archivedon::redirect_map::RedirectMap;
+use crate::redirect_map::RedirectMap;
 use log::error;
+use url::Url;
 use warp::filters::path::FullPath;
 
+use crate::server::env::index::Indexed;
+use crate::server::env::site::Site;
 use crate::server::env::Env;
 use crate::server::handler;
 
@@ -13,68 +17,92 @@ pub async fn handle(
     path: FullPath,
     accept: Option<String>,
 ) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
-    let resource_path = env.resource_path.redirect_map_path(&host, path.as_str());
-    match tokio::fs::try_exists(&resource_path).await {
-        Ok(false) => return Err(warp::reject()),
+    let site = match env.site(Some(&host)) {
+        None => return Err(warp::reject()),
+        Some(x) => x,
+    };
+
+    let redirect_url = match find_redirect_url(&site, &host, &path, accept.as_deref()).await {
+        Err(reply) => return Ok(reply),
+        Ok(None) => {
+            env.metrics.record_redirect_lookup(&host, false);
+            return Err(warp::reject());
+        }
+        Ok(Some(x)) => {
+            env.metrics.record_redirect_lookup(&host, true);
+            x
+        }
+    };
+
+    // No validators are given, since preconditions are ignored for responses other than 2xx
+    // (RFC 9110, Section 13.2.1). Caches reuse redirects by `Cache-Control` only.
+    let reply = warp::reply::reply();
+    let reply = warp::reply::with_status(reply, env.redirect_status(&host));
+    let reply = warp::reply::with_header(reply, "Location", redirect_url.to_string());
+    let reply = warp::reply::with_header(reply, "Vary", "Accept");
+
+    Ok(handler::with_cache_control(
+        Box::new(reply),
+        env.cache.redirect_max_age,
+    ))
+}
+
+async fn find_redirect_url(
+    site: &Site,
+    host: &str,
+    path: &FullPath,
+    accept: Option<&str>,
+) -> Result<Option<Url>, Box<dyn warp::Reply>> {
+    let resource_path = site.resource_path.redirect_map_path(host, path.as_str());
+    let index = site.index();
+    let resource = match index.indexed(|files| &files.redirect_maps, &resource_path) {
+        Indexed::Found(x) => x.clone(),
+        Indexed::Missing => return Ok(None),
+        Indexed::NotIndexed => match read_resource(&resource_path).await? {
+            None => return Ok(None),
+            Some(x) => x,
+        },
+    };
+
+    Ok(resource.negotiate(accept))
+}
+
+async fn read_resource(resource_path: &Path) -> Result<Option<RedirectMap>, Box<dyn warp::Reply>> {
+    match tokio::fs::try_exists(resource_path).await {
+        Ok(false) => return Ok(None),
         Err(err) => {
             error!(
                 "Failed to access resource path: path={}, err={}",
-                &resource_path.display(),
+                resource_path.display(),
                 err
             );
-            return Ok(handler::internal_server_error());
+            return Err(handler::internal_server_error());
         }
         Ok(true) => {
             // do nothing
         }
     }
 
-    let resource = match tokio::fs::read(&resource_path).await {
+    let resource = match tokio::fs::read(resource_path).await {
         Ok(x) => x,
         Err(err) => {
             error!(
                 "Failed to access resource path: path={}, err={}",
-                &resource_path.display(),
+                resource_path.display(),
                 err
             );
-            return Ok(handler::internal_server_error());
+            return Err(handler::internal_server_error());
         }
     };
-    let resource: RedirectMap = match serde_json::from_slice(&resource) {
-        Ok(x) => x,
+    match serde_json::from_slice(&resource) {
+        Ok(x) => Ok(Some(x)),
         Err(err) => {
             error!(
                 "Failed to deserialize resource: path={}, err={}",
-                &resource_path.display(),
+                resource_path.display(),
                 err
             );
-            return Err(warp::reject());
-        }
-    };
-
-    let mut redirect_url_opt = None;
-    // TODO: full support accept header syntax
-    if let Some(accept) = accept {
-        if let Some(url) = resource.get_entry(&accept) {
-            redirect_url_opt = Some(url)
-        }
-    }
-
-    if redirect_url_opt.is_none() {
-        if let Some(url) = resource.get_entry("*/*") {
-            redirect_url_opt = Some(url)
+            Ok(None)
         }
     }
-
-    let redirect_url = match redirect_url_opt {
-        None => return Err(warp::reject()),
-        Some(x) => x,
-    };
-
-    let reply = warp::reply::reply();
-    let reply = warp::reply::with_status(reply, warp::http::StatusCode::MOVED_PERMANENTLY);
-    let reply = warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*");
-    let reply = warp::reply::with_header(reply, "Location", redirect_url.to_string());
-
-    Ok(Box::new(reply))
 }
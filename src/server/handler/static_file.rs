@@ -0,0 +1,140 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::compression::{self, Encoding};
+use crate::entity_tag;
+use log::warn;
+use tokio_util::io::ReaderStream;
+use warp::filters::fs::File;
+use warp::http::{header, HeaderValue, StatusCode};
+use warp::hyper::Body;
+use warp::Reply;
+
+use crate::server::env::site::Site;
+use crate::server::env::Env;
+use crate::server::handler;
+
+/**
+ * Serve a file of the archive with its entity tag, or `304 Not Modified` if it is not modified.
+ *
+ * A precompressed sibling such as `index.html.br` is served instead if the client accepts its encoding.
+ */
+pub async fn handle(
+    env: Arc<Env>,
+    site: Arc<Site>,
+    accept_encoding: Option<String>,
+    if_none_match: Option<String>,
+    file: File,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let mime = mime_guess::from_path(file.path()).first_or_octet_stream();
+    let max_age = if mime.essence_str() == "text/html" {
+        env.cache.html_max_age
+    } else if mime.subtype() == "json" || mime.suffix().map(|x| x.as_str()) == Some("json") {
+        env.cache.static_json_max_age
+    } else {
+        env.cache.static_max_age
+    };
+
+    let encodings = precompressed_encodings(file.path()).await;
+    if !encodings.is_empty() {
+        let encoding_opt = accept_encoding
+            .as_deref()
+            .and_then(|x| compression::negotiate(x, &encodings));
+        let reply = match encoding_opt {
+            None => handle_identity(&site, if_none_match, file).await,
+            Some(encoding) => handle_encoded(&site, encoding, if_none_match, file).await,
+        };
+        let reply = warp::reply::with_header(reply, "Vary", "Accept-Encoding");
+        return Ok(handler::with_cache_control(Box::new(reply), max_age));
+    }
+
+    let reply = handle_identity(&site, if_none_match, file).await;
+    Ok(handler::with_cache_control(reply, max_age))
+}
+
+async fn handle_identity(
+    site: &Site,
+    if_none_match: Option<String>,
+    file: File,
+) -> Box<dyn warp::Reply> {
+    let etag = match site.etags.get(file.path()).await {
+        None => return Box::new(file),
+        Some(x) => x,
+    };
+
+    match if_none_match {
+        Some(if_none_match) if entity_tag::if_none_match(&if_none_match, &etag) => {
+            handler::not_modified(&etag)
+        }
+        _ => Box::new(warp::reply::with_header(file, "ETag", etag)),
+    }
+}
+
+/**
+ * Serve the precompressed sibling of the file as a whole, since ranges of encoded contents are rarely useful.
+ */
+async fn handle_encoded(
+    site: &Site,
+    encoding: Encoding,
+    if_none_match: Option<String>,
+    file: File,
+) -> Box<dyn warp::Reply> {
+    let encoded_path = encoding.sibling_path(file.path());
+    let etag = match site.etags.get(&encoded_path).await {
+        None => return handle_identity(site, if_none_match, file).await,
+        Some(x) => x,
+    };
+    if let Some(if_none_match) = &if_none_match {
+        if entity_tag::if_none_match(if_none_match, &etag) {
+            return handler::not_modified(&etag);
+        }
+    }
+
+    // Responses other than 200 OK such as 304 by `If-Modified-Since` are left to the original file.
+    let mut response = file.into_response();
+    if response.status() != StatusCode::OK {
+        return Box::new(response);
+    }
+    let (content, content_len) = match open_with_len(&encoded_path).await {
+        Ok(x) => x,
+        Err(err) => {
+            warn!(
+                "Failed to read a precompressed file: path={}, err={}",
+                encoded_path.display(),
+                err
+            );
+            return Box::new(response);
+        }
+    };
+
+    let headers = response.headers_mut();
+    headers.remove(header::ACCEPT_RANGES);
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(content_len));
+    headers.insert(
+        header::CONTENT_ENCODING,
+        HeaderValue::from_static(encoding.as_str()),
+    );
+    if let Ok(etag) = HeaderValue::from_str(&etag) {
+        headers.insert(header::ETAG, etag);
+    }
+    // Streamed, since precompressed files such as media may be large.
+    *response.body_mut() = Body::wrap_stream(ReaderStream::new(content));
+    Box::new(response)
+}
+
+async fn open_with_len(path: &Path) -> std::io::Result<(tokio::fs::File, u64)> {
+    let file = tokio::fs::File::open(path).await?;
+    let len = file.metadata().await?.len();
+    Ok((file, len))
+}
+
+async fn precompressed_encodings(path: &Path) -> Vec<Encoding> {
+    let mut encodings = vec![];
+    for encoding in Encoding::ALL {
+        match tokio::fs::metadata(encoding.sibling_path(path)).await {
+            Ok(metadata) if metadata.is_file() => encodings.push(encoding),
+            _ => {}
+        }
+    }
+    encodings
+}
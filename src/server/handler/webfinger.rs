@@ -1,16 +1,42 @@
-use archivedon::webfinger;
+use crate::entity_tag;
+use crate::webfinger;
 use log::error;
+use serde::de::DeserializeOwned;
+use std::error::Error;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::server::env::index::{self, Indexed, ResourceIndex};
+use crate::server::env::site::Site;
 use crate::server::env::Env;
 use crate::server::handler;
 
 pub async fn handle(
     env: Arc<Env>,
+    host_opt: Option<String>,
+    accept_encoding: Option<String>,
+    if_none_match: Option<String>,
+    if_modified_since: Option<String>,
     params: Vec<(String, String)>,
 ) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
     let params = QueryParams::parse(params)?;
-    handle_resource(env, &params.resource, params.rel).await
+    let site = match env.site(host_opt.as_deref()) {
+        None => return Ok(handler::not_found()),
+        Some(x) => x,
+    };
+    handle_resource(
+        &site,
+        &params.resource,
+        params.rel,
+        accept_encoding,
+        Conditions {
+            if_none_match,
+            if_modified_since,
+        },
+        env.cache.webfinger_max_age,
+    )
+    .await
 }
 
 #[derive(Debug)]
@@ -45,50 +71,31 @@ impl QueryParams {
     }
 }
 
+/**
+ * Headers of conditional requests.
+ */
+struct Conditions {
+    if_none_match: Option<String>,
+    if_modified_since: Option<String>,
+}
+
 async fn handle_resource(
-    env: Arc<Env>,
+    site: &Site,
     resource: &str,
     rel: Vec<String>,
+    accept_encoding: Option<String>,
+    conditions: Conditions,
+    max_age: Option<Duration>,
 ) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
-    let resource_path = env.resource_path.webfinger_path(resource);
-
-    match tokio::fs::try_exists(&resource_path).await {
-        Ok(false) => return Ok(handler::not_found()),
+    let (mut resource, resource_path) = match find_resource(site, resource).await {
         Err(err) => {
-            error!(
-                "Failed to access resource path: path={}, err={}",
-                &resource_path.display(),
-                err
-            );
-            return Ok(handler::internal_server_error());
-        }
-        Ok(true) => {
-            // do nothing
-        }
-    }
-
-    let resource = match tokio::fs::read(&resource_path).await {
-        Ok(x) => x,
-        Err(err) => {
-            error!(
-                "Failed to access resource path: path={}, err={}",
-                &resource_path.display(),
-                err
-            );
+            error!("Failed to find resource: resource={resource}, err={err}");
             return Ok(handler::internal_server_error());
         }
+        Ok(None) => return Ok(handler::not_found()),
+        Ok(Some(x)) => x,
     };
-    let mut resource: webfinger::resource::Resource = match serde_json::from_slice(&resource) {
-        Ok(x) => x,
-        Err(err) => {
-            error!(
-                "Failed to deserialize resource: path={}, err={}",
-                &resource_path.display(),
-                err
-            );
-            return Ok(handler::bad_request());
-        }
-    };
+    let modified_opt = index::modified(&resource_path);
     if !rel.is_empty() {
         resource.links = match resource.links {
             None => None,
@@ -104,10 +111,99 @@ async fn handle_resource(
         }
     }
 
-    let reply = warp::reply::json(&resource);
-    let reply = warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*");
-    let reply =
-        warp::reply::with_header(reply, "Content-Type", "application/jrd+json; charset=utf-8");
+    let body = match serde_json::to_vec(&resource) {
+        Ok(x) => x,
+        Err(err) => {
+            error!(
+                "Failed to serialize resource: resource={}, err={}",
+                resource.subject, err
+            );
+            return Ok(handler::internal_server_error());
+        }
+    };
+    // The entity tag is of the encoded body, since each encoding is a different representation.
+    let (body, encoding) = handler::encode_body(body, accept_encoding.as_deref());
+    let etag = entity_tag::from_content(&body);
+    // `If-Modified-Since` is evaluated only without `If-None-Match`, as RFC 9110 says.
+    let not_modified = match (&conditions.if_none_match, &conditions.if_modified_since) {
+        (Some(if_none_match), _) => entity_tag::if_none_match(if_none_match, &etag),
+        (None, Some(if_modified_since)) => modified_opt
+            .is_some_and(|modified| entity_tag::not_modified_since(if_modified_since, modified)),
+        (None, None) => false,
+    };
+    let reply = if not_modified {
+        let reply =
+            warp::reply::with_header(handler::not_modified(&etag), "Vary", "Accept-Encoding");
+        Box::new(reply) as Box<dyn warp::Reply>
+    } else {
+        let reply = handler::encoded_reply(body, "application/jrd+json; charset=utf-8", encoding);
+        Box::new(warp::reply::with_header(reply, "ETag", etag))
+    };
+    let reply = match modified_opt {
+        None => reply,
+        Some(modified) => Box::new(warp::reply::with_header(
+            reply,
+            "Last-Modified",
+            httpdate::fmt_http_date(modified),
+        )),
+    };
+
+    Ok(handler::with_cache_control(reply, max_age))
+}
+
+/**
+ * Find the resource by its subject, or by its alias, with the path of the resource file.
+ */
+async fn find_resource(
+    site: &Site,
+    resource: &str,
+) -> Result<Option<(webfinger::resource::Resource, PathBuf)>, Box<dyn Error>> {
+    let index = site.index();
+    let normalized_resource = webfinger::acct::normalize_resource(resource);
+
+    // Raw resources are also looked up for archives saved without normalization.
+    for subject in [normalized_resource.as_str(), resource] {
+        let resource_path = site.resource_path.webfinger_path(subject);
+        if let Some(resource) = load_resource(&index, &resource_path).await? {
+            return Ok(Some((resource, resource_path)));
+        }
+    }
+
+    let alias_path = site
+        .resource_path
+        .webfinger_alias_path(&normalized_resource);
+    let alias: webfinger::alias::Alias =
+        match index.indexed(|files| &files.webfinger_aliases, &alias_path) {
+            Indexed::Found(x) => x.clone(),
+            Indexed::Missing => return Ok(None),
+            Indexed::NotIndexed => match read_json_file(&alias_path).await? {
+                None => return Ok(None),
+                Some(x) => x,
+            },
+        };
 
-    Ok(Box::new(reply))
+    let resource_path = site
+        .resource_path
+        .webfinger_path(&webfinger::acct::normalize_resource(&alias.subject));
+    Ok(load_resource(&index, &resource_path)
+        .await?
+        .map(|resource| (resource, resource_path)))
+}
+
+async fn load_resource(
+    index: &ResourceIndex,
+    resource_path: &Path,
+) -> Result<Option<webfinger::resource::Resource>, Box<dyn Error>> {
+    match index.indexed(|files| &files.webfinger_resources, resource_path) {
+        Indexed::Found(x) => Ok(Some(x.clone())),
+        Indexed::Missing => Ok(None),
+        Indexed::NotIndexed => read_json_file(resource_path).await,
+    }
+}
+
+async fn read_json_file<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, Box<dyn Error>> {
+    if !tokio::fs::try_exists(path).await? {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_slice(&tokio::fs::read(path).await?)?))
 }
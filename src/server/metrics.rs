@@ -0,0 +1,109 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    sync::Mutex,
+    time::Duration,
+};
+
+/**
+ * A label for hosts beyond `MAX_DOMAIN_LABELS`, since Host headers are given by clients.
+ */
+const OTHER_DOMAIN_LABEL: &str = "_other";
+const MAX_DOMAIN_LABELS: usize = 256;
+
+/**
+ * Counters of served requests, rendered in the Prometheus text format.
+ */
+#[derive(Default)]
+pub struct Metrics {
+    inner: Mutex<MetricsInner>,
+}
+
+#[derive(Default)]
+struct MetricsInner {
+    requests: BTreeMap<(&'static str, u16), u64>,
+    request_durations: BTreeMap<&'static str, (f64, u64)>,
+    redirect_lookups: BTreeMap<(String, bool), u64>,
+    domains: BTreeSet<String>,
+}
+
+impl Metrics {
+    pub fn record_request(&self, route: &'static str, status: u16, elapsed: Duration) {
+        let mut inner = self.lock();
+        *inner.requests.entry((route, status)).or_default() += 1;
+        let duration = inner.request_durations.entry(route).or_default();
+        duration.0 += elapsed.as_secs_f64();
+        duration.1 += 1;
+    }
+
+    pub fn record_redirect_lookup(&self, host: &str, hit: bool) {
+        let mut inner = self.lock();
+        let domain = if inner.domains.contains(host) {
+            host.to_string()
+        } else if inner.domains.len() < MAX_DOMAIN_LABELS {
+            inner.domains.insert(host.to_string());
+            host.to_string()
+        } else {
+            OTHER_DOMAIN_LABEL.to_string()
+        };
+        *inner.redirect_lookups.entry((domain, hit)).or_default() += 1;
+    }
+
+    pub fn render(&self) -> String {
+        let inner = self.lock();
+        let mut out = String::new();
+
+        out.push_str("# HELP archivedon_requests_total Served requests by route and status.\n");
+        out.push_str("# TYPE archivedon_requests_total counter\n");
+        for ((route, status), count) in &inner.requests {
+            let _ = writeln!(
+                out,
+                "archivedon_requests_total{{route=\"{route}\",status=\"{status}\"}} {count}"
+            );
+        }
+
+        out.push_str(
+            "# HELP archivedon_request_duration_seconds Latencies of served requests by route.\n",
+        );
+        out.push_str("# TYPE archivedon_request_duration_seconds summary\n");
+        for (route, (sum, count)) in &inner.request_durations {
+            let _ = writeln!(
+                out,
+                "archivedon_request_duration_seconds_sum{{route=\"{route}\"}} {sum}"
+            );
+            let _ = writeln!(
+                out,
+                "archivedon_request_duration_seconds_count{{route=\"{route}\"}} {count}"
+            );
+        }
+
+        out.push_str(
+            "# HELP archivedon_redirect_lookups_total Lookups of redirect maps by old domain.\n",
+        );
+        out.push_str("# TYPE archivedon_redirect_lookups_total counter\n");
+        for ((domain, hit), count) in &inner.redirect_lookups {
+            let _ = writeln!(
+                out,
+                "archivedon_redirect_lookups_total{{domain=\"{}\",result=\"{}\"}} {count}",
+                escape_label_value(domain),
+                if *hit { "hit" } else { "miss" },
+            );
+        }
+
+        out
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MetricsInner> {
+        match self.inner.lock() {
+            Ok(inner) => inner,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -1,3 +1,6 @@
 pub mod cmd;
+pub mod config;
 pub mod env;
 pub mod handler;
+pub mod metrics;
+pub mod tls;
@@ -0,0 +1,222 @@
+use std::{
+    error::Error,
+    fs, io,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use futures_util::Stream;
+use log::{debug, error, info};
+use rustls::{
+    server::{ClientHello, ResolvesServerCert},
+    sign::{self, CertifiedKey},
+    Certificate, PrivateKey, ServerConfig,
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+use tokio_rustls::{server::TlsStream, TlsAcceptor};
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/**
+ * A pair of PEM files of a certificate chain and its private key.
+ */
+pub struct CertificateFile {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+struct LoadedCertificate {
+    certified_key: Arc<CertifiedKey>,
+    end_entity_der: Vec<u8>,
+}
+
+struct LoadedCertificates {
+    certificates: Vec<LoadedCertificate>,
+    modified: Vec<Option<SystemTime>>,
+}
+
+/**
+ * Select a certificate by SNI from multiple certificates, which can be reloaded while serving.
+ *
+ * The first certificate is used if no certificate matches with the server name.
+ */
+pub struct CertResolver {
+    files: Vec<CertificateFile>,
+    loaded: RwLock<Arc<LoadedCertificates>>,
+}
+
+impl CertResolver {
+    pub fn load(files: Vec<CertificateFile>) -> Result<Arc<Self>, Box<dyn Error>> {
+        if files.is_empty() {
+            return Err("No TLS certificates are given.".into());
+        }
+        let loaded = load_certificates(&files)?;
+        Ok(Arc::new(Self {
+            files,
+            loaded: RwLock::new(Arc::new(loaded)),
+        }))
+    }
+
+    fn loaded(&self) -> Arc<LoadedCertificates> {
+        match self.loaded.read() {
+            Ok(loaded) => loaded.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /**
+     * Reload certificates. The current certificates are kept if some of files are broken.
+     */
+    pub fn reload(&self) {
+        let loaded = match load_certificates(&self.files) {
+            Ok(x) => Arc::new(x),
+            Err(err) => {
+                error!("Failed to reload TLS certificates: err={err}");
+                return;
+            }
+        };
+        match self.loaded.write() {
+            Ok(mut current) => *current = loaded,
+            Err(poisoned) => *poisoned.into_inner() = loaded,
+        }
+        info!("Reloaded TLS certificates.");
+    }
+
+    pub fn is_updated(&self) -> bool {
+        self.loaded().modified != modified_times(&self.files)
+    }
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        let loaded = self.loaded();
+        let matched = client_hello.server_name().and_then(|server_name| {
+            let subject_name = webpki::SubjectNameRef::try_from_ascii_str(server_name).ok()?;
+            loaded.certificates.iter().find(|certificate| {
+                match webpki::EndEntityCert::try_from(certificate.end_entity_der.as_slice()) {
+                    Ok(cert) => cert.verify_is_valid_for_subject_name(subject_name).is_ok(),
+                    Err(_) => false,
+                }
+            })
+        });
+        matched
+            .or_else(|| loaded.certificates.first())
+            .map(|certificate| certificate.certified_key.clone())
+    }
+}
+
+fn modified_times(files: &[CertificateFile]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .flat_map(|file| [&file.cert_path, &file.key_path])
+        .map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+        .collect()
+}
+
+fn load_certificates(files: &[CertificateFile]) -> Result<LoadedCertificates, Box<dyn Error>> {
+    let modified = modified_times(files);
+    let mut certificates = vec![];
+    for file in files {
+        certificates.push(load_certificate(file)?);
+    }
+    Ok(LoadedCertificates {
+        certificates,
+        modified,
+    })
+}
+
+fn load_certificate(file: &CertificateFile) -> Result<LoadedCertificate, Box<dyn Error>> {
+    let cert_pem = fs::read(&file.cert_path)?;
+    let certs = rustls_pemfile::certs(&mut cert_pem.as_slice())?;
+    let end_entity_der = match certs.first() {
+        Some(x) => x.clone(),
+        None => {
+            return Err(format!(
+                "No certificates are found: path={}",
+                file.cert_path.display()
+            )
+            .into())
+        }
+    };
+
+    let key_pem = fs::read(&file.key_path)?;
+    let mut key_reader = key_pem.as_slice();
+    let key_der = loop {
+        match rustls_pemfile::read_one(&mut key_reader)? {
+            Some(rustls_pemfile::Item::PKCS8Key(der))
+            | Some(rustls_pemfile::Item::RSAKey(der))
+            | Some(rustls_pemfile::Item::ECKey(der)) => break der,
+            Some(_) => continue,
+            None => {
+                return Err(format!(
+                    "No private keys are found: path={}",
+                    file.key_path.display()
+                )
+                .into())
+            }
+        }
+    };
+    let signing_key = sign::any_supported_type(&PrivateKey(key_der))?;
+
+    Ok(LoadedCertificate {
+        certified_key: Arc::new(CertifiedKey::new(
+            certs.into_iter().map(Certificate).collect(),
+            signing_key,
+        )),
+        end_entity_der,
+    })
+}
+
+/**
+ * Accept TLS connections. Handshakes are processed concurrently not to block accepting.
+ */
+pub async fn incoming(
+    sock_addr: SocketAddr,
+    resolver: Arc<CertResolver>,
+) -> Result<impl Stream<Item = Result<TlsStream<TcpStream>, io::Error>>, Box<dyn Error>> {
+    let mut config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_cert_resolver(resolver);
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+
+    let listener = TcpListener::bind(sock_addr).await?;
+    let (tx, rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer_addr) = match listener.accept().await {
+                Ok(x) => x,
+                Err(err) => {
+                    error!("Failed to accept a connection: err={err}");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            let acceptor = acceptor.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => {
+                        let _ = tx.send(stream).await;
+                    }
+                    Ok(Err(err)) => {
+                        debug!("Failed TLS handshake: peer={peer_addr}, err={err}");
+                    }
+                    Err(_) => {
+                        debug!("Timed out TLS handshake: peer={peer_addr}");
+                    }
+                }
+            });
+        }
+    });
+
+    Ok(futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|stream| (Ok(stream), rx))
+    }))
+}
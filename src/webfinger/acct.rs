@@ -0,0 +1,111 @@
+use std::error::Error;
+use std::fmt;
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use url::Url;
+
+/**
+ * Characters to be percent-encoded in the user part, i.e. other than unreserved and sub-delims.
+ *
+ * ref: https://datatracker.ietf.org/doc/html/rfc7565#section-7
+ */
+const USER_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b':')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'@')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/**
+ * A normalized acct URI: the user part is percent-decoded and case-folded,
+ * and the host is converted to lower-cased ASCII (punycode).
+ *
+ * ref: https://datatracker.ietf.org/doc/html/rfc7565
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Acct {
+    pub user: String,
+    pub host: String,
+}
+
+impl Acct {
+    /**
+     * Parse `acct:user@host`, also accepting `user@host` and `@user@host` without the scheme.
+     */
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        let value = value.trim();
+        let without_scheme = match value.get(..5) {
+            Some(scheme) if scheme.eq_ignore_ascii_case("acct:") => &value[5..],
+            _ => value.strip_prefix('@').unwrap_or(value),
+        };
+
+        let Some((user, host)) = without_scheme.rsplit_once('@') else {
+            return Err(format!("acct URI should contain a host: {value}").into());
+        };
+        let user = percent_decode_str(user).decode_utf8()?.to_lowercase();
+        let host = percent_decode_str(host).decode_utf8()?;
+        if user.is_empty() || host.is_empty() {
+            return Err(format!("acct URI should contain a user and a host: {value}").into());
+        }
+
+        let (host_name, port_opt) = match host.rsplit_once(':') {
+            Some((host_name, port))
+                if !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                (host_name, Some(port))
+            }
+            _ => (host.as_ref(), None),
+        };
+        let host_name = match idna::domain_to_ascii(host_name) {
+            Ok(x) if !x.is_empty() => x,
+            _ => return Err(format!("acct URI contains an illegal host: {value}").into()),
+        };
+        let host = match port_opt {
+            None => host_name,
+            Some(port) => format!("{host_name}:{port}"),
+        };
+
+        Ok(Self { user, host })
+    }
+}
+
+impl fmt::Display for Acct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "acct:{}@{}",
+            utf8_percent_encode(&self.user, USER_ENCODE_SET),
+            self.host
+        )
+    }
+}
+
+/**
+ * Normalize a WebFinger resource to look up: acct URIs are normalized as `Acct`,
+ * and other URLs are normalized by URL parsing.
+ */
+pub fn normalize_resource(resource: &str) -> String {
+    let is_url = resource.contains("://");
+    if !is_url {
+        if let Ok(acct) = Acct::parse(resource) {
+            return acct.to_string();
+        }
+    }
+    match Url::parse(resource) {
+        Ok(url) => url.to_string(),
+        Err(_) => resource.to_string(),
+    }
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/**
+ * An index entry from an alias, e.g. an actor ID or a profile URL, to the subject of the resource.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Alias {
+    pub subject: String,
+}
@@ -1 +1,3 @@
+pub mod acct;
+pub mod alias;
 pub mod resource;
@@ -0,0 +1,86 @@
+use std::io::Read;
+use std::path::Path;
+
+use archivedon::compression::{self, Encoding, Level};
+
+#[test]
+fn prefer_brotli_at_same_quality() {
+    assert_eq!(
+        compression::negotiate("gzip, deflate, br", &Encoding::ALL),
+        Some(Encoding::Brotli)
+    );
+    assert_eq!(
+        compression::negotiate("gzip, deflate, br", &[Encoding::Gzip]),
+        Some(Encoding::Gzip)
+    );
+}
+
+#[test]
+fn respect_qualities_and_wildcard() {
+    assert_eq!(
+        compression::negotiate("br;q=0.5, gzip", &Encoding::ALL),
+        Some(Encoding::Gzip)
+    );
+    assert_eq!(
+        compression::negotiate("br;q=0, *", &Encoding::ALL),
+        Some(Encoding::Gzip)
+    );
+    assert_eq!(compression::negotiate("identity", &Encoding::ALL), None);
+    assert_eq!(compression::negotiate("", &Encoding::ALL), None);
+}
+
+#[test]
+fn parse_as_accept_headers() {
+    // A specific coding overrides the wildcard regardless of the order.
+    assert_eq!(
+        compression::negotiate("*;q=0.1, gzip;q=0.8", &Encoding::ALL),
+        Some(Encoding::Gzip)
+    );
+    assert_eq!(
+        compression::negotiate("x-gzip, BR;q=0.5", &Encoding::ALL),
+        Some(Encoding::Gzip)
+    );
+    // Codings with invalid qualities are ignored as in Accept.
+    assert_eq!(
+        compression::negotiate("br;q=2, gzip;q=0.5", &Encoding::ALL),
+        Some(Encoding::Gzip)
+    );
+    assert_eq!(compression::negotiate("br;q=abc", &Encoding::ALL), None);
+    assert_eq!(compression::negotiate("*;q=0", &Encoding::ALL), None);
+}
+
+#[test]
+fn compress_and_decompress() {
+    let content = b"{\"type\":\"Note\",\"content\":\"Hello, hello, hello\"}";
+
+    let gzip = Encoding::Gzip.compress(content, Level::Best).unwrap();
+    let mut decoded = vec![];
+    flate2::read::GzDecoder::new(gzip.as_slice())
+        .read_to_end(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, content);
+
+    let brotli = Encoding::Brotli.compress(content, Level::Fast).unwrap();
+    let mut decoded = vec![];
+    brotli::Decompressor::new(brotli.as_slice(), 4096)
+        .read_to_end(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, content);
+}
+
+#[test]
+fn sibling_paths_and_compressible_types() {
+    let path = Path::new("static/users/a.json");
+
+    assert_eq!(
+        Encoding::Brotli.sibling_path(path),
+        Path::new("static/users/a.json.br")
+    );
+    assert_eq!(
+        Encoding::Gzip.sibling_path(path),
+        Path::new("static/users/a.json.gz")
+    );
+    assert!(compression::is_compressible(path));
+    assert!(compression::is_compressible(Path::new("a.html")));
+    assert!(!compression::is_compressible(Path::new("a.png")));
+}
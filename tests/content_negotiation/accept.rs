@@ -0,0 +1,111 @@
+use archivedon::content_negotiation::accept::{Accept, AcceptTokens, MediaType};
+
+#[test]
+fn parse_qualities_and_params() {
+    let accept =
+        Accept::parse("text/html,application/xhtml+xml;q=0.9, */*;q=0.8, bad, text/plain;q=2");
+
+    assert_eq!(accept.ranges.len(), 3);
+    assert_eq!(accept.ranges[0].media_type.essence(), "text/html");
+    assert_eq!(accept.ranges[0].quality, 1.0);
+    assert_eq!(
+        accept.ranges[1].media_type.essence(),
+        "application/xhtml+xml"
+    );
+    assert_eq!(accept.ranges[1].quality, 0.9);
+    assert!(accept.ranges[1].media_type.params.is_empty());
+    assert_eq!(accept.ranges[2].media_type.essence(), "*/*");
+    assert_eq!(accept.ranges[2].quality, 0.8);
+}
+
+#[test]
+fn quality_by_most_specific_range() {
+    let accept = Accept::parse("text/*;q=0.3, text/html;q=0.7, */*;q=0.5");
+
+    assert_eq!(accept.quality(&MediaType::parse("text/html").unwrap()), 0.7);
+    assert_eq!(
+        accept.quality(&MediaType::parse("text/plain").unwrap()),
+        0.3
+    );
+    assert_eq!(accept.quality(&MediaType::parse("image/png").unwrap()), 0.5);
+}
+
+#[test]
+fn activity_json_equivalence() {
+    let accept =
+        Accept::parse(r#"application/ld+json; profile="https://www.w3.org/ns/activitystreams""#);
+
+    assert_eq!(
+        accept.negotiate(&["text/html", "application/activity+json"]),
+        Some("application/activity+json")
+    );
+
+    let accept = Accept::parse(r#"application/ld+json; profile="https://example.com/other""#);
+
+    assert_eq!(accept.negotiate(&["application/activity+json"]), None);
+}
+
+#[test]
+fn quoted_separators() {
+    let accept = Accept::parse(
+        r#"application/ld+json; profile="https://example.com/a, https://www.w3.org/ns/activitystreams"; q=0.8, text/html; q=0.5"#,
+    );
+
+    assert_eq!(accept.ranges.len(), 2);
+    assert_eq!(
+        accept.ranges[0].media_type.param("profile"),
+        Some("https://example.com/a, https://www.w3.org/ns/activitystreams")
+    );
+    assert_eq!(accept.ranges[0].quality, 0.8);
+    assert_eq!(accept.ranges[1].media_type.essence(), "text/html");
+    assert_eq!(
+        accept.negotiate(&["text/html", "application/activity+json"]),
+        Some("application/activity+json")
+    );
+
+    let media_type = MediaType::parse(r#"text/plain; note="a; \"b\""; charset=utf-8"#).unwrap();
+    assert_eq!(media_type.param("note"), Some(r#"a; "b""#));
+    assert_eq!(media_type.param("charset"), Some("utf-8"));
+
+    let accept = AcceptTokens::parse(r#"gzip; ext="a, br"; q=0.5, br"#);
+    assert_eq!(accept.ranges.len(), 2);
+    assert_eq!(accept.quality("gzip"), 0.5);
+}
+
+#[test]
+fn negotiate_prefers_quality_then_specificity() {
+    let accept = Accept::parse("application/activity+json;q=0.5, text/html");
+
+    assert_eq!(
+        accept.negotiate(&["application/activity+json", "text/html"]),
+        Some("text/html")
+    );
+
+    let accept = Accept::parse("*/*, text/html");
+
+    assert_eq!(
+        accept.negotiate(&["application/activity+json", "text/html"]),
+        Some("text/html")
+    );
+    assert_eq!(
+        accept.negotiate_explicitly(&["application/activity+json"]),
+        None
+    );
+}
+
+#[test]
+fn accept_tokens() {
+    let accept = AcceptTokens::parse("gzip;q=0.5, BR, *;q=0.1, identity;q=0, deflate;q=x");
+
+    assert_eq!(accept.quality("br"), 1.0);
+    assert_eq!(accept.quality("gzip"), 0.5);
+    assert_eq!(accept.quality("zstd"), 0.1);
+    assert_eq!(accept.quality("identity"), 0.0);
+    // Items with invalid qualities are ignored, so the wildcard is applied.
+    assert_eq!(accept.quality("deflate"), 0.1);
+
+    assert_eq!(accept.negotiate(&["gzip", "br"]), Some("br"));
+    assert_eq!(accept.negotiate(&["identity"]), None);
+    assert_eq!(accept.negotiate(&["zstd", "deflate"]), Some("zstd"));
+    assert_eq!(AcceptTokens::parse("").negotiate(&["gzip"]), None);
+}
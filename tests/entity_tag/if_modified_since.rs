@@ -0,0 +1,44 @@
+use std::time::{Duration, SystemTime};
+
+use archivedon::entity_tag;
+use sha2::{Digest, Sha256};
+
+#[test]
+fn streamed_digest() {
+    let mut digest = Sha256::new();
+    digest.update(b"{\"a\":");
+    digest.update(b"1}");
+    assert_eq!(
+        entity_tag::from_digest(digest),
+        entity_tag::from_content(b"{\"a\":1}")
+    );
+}
+
+#[test]
+fn compare_in_seconds() {
+    // Sun, 06 Nov 1994 08:49:37 GMT
+    let date = SystemTime::UNIX_EPOCH + Duration::from_secs(784111777);
+    let header = "Sun, 06 Nov 1994 08:49:37 GMT";
+
+    assert!(entity_tag::not_modified_since(header, date));
+    assert!(entity_tag::not_modified_since(
+        header,
+        date + Duration::from_millis(500)
+    ));
+    assert!(entity_tag::not_modified_since(
+        header,
+        date - Duration::from_secs(60)
+    ));
+    assert!(!entity_tag::not_modified_since(
+        header,
+        date + Duration::from_secs(1)
+    ));
+}
+
+#[test]
+fn invalid_dates() {
+    let date = SystemTime::UNIX_EPOCH;
+
+    assert!(!entity_tag::not_modified_since("", date));
+    assert!(!entity_tag::not_modified_since("yesterday", date));
+}
@@ -0,0 +1,23 @@
+use archivedon::entity_tag;
+
+#[test]
+fn strong_tag_by_content() {
+    let etag = entity_tag::from_content(b"{}");
+
+    assert!(etag.starts_with('"') && etag.ends_with('"'));
+    assert_eq!(etag.len(), 34);
+    assert_eq!(etag, entity_tag::from_content(b"{}"));
+    assert_ne!(etag, entity_tag::from_content(b"[]"));
+}
+
+#[test]
+fn match_by_weak_comparison() {
+    let etag = "\"abc\"";
+
+    assert!(entity_tag::if_none_match("\"abc\"", etag));
+    assert!(entity_tag::if_none_match("W/\"abc\"", etag));
+    assert!(entity_tag::if_none_match("\"xyz\", W/\"abc\"", etag));
+    assert!(entity_tag::if_none_match("*", etag));
+    assert!(!entity_tag::if_none_match("\"xyz\"", etag));
+    assert!(!entity_tag::if_none_match("", etag));
+}
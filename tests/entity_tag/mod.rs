@@ -0,0 +1,2 @@
+pub mod if_modified_since;
+pub mod if_none_match;
@@ -0,0 +1,239 @@
+use activitist::model as ap_model;
+use archivedon::fetch::misskey_export::{Note, NoteContext};
+
+const CONTEXT: NoteContext = NoteContext {
+    domain: "misskey.example",
+    actor_id: "https://misskey.example/users/9abc",
+    followers_id: "https://misskey.example/users/9abc/followers",
+};
+
+fn parse_note(data: &str) -> Note {
+    serde_json::from_str(data).unwrap()
+}
+
+fn object_of(activity: &ap_model::Object) -> &ap_model::Object {
+    match activity.activity_items.object.first() {
+        Some(ap_model::ObjectOrLink::Object(object)) => object,
+        _ => panic!("The activity should have an embedded object."),
+    }
+}
+
+fn href_of(object_or_link: &ap_model::ObjectOrLink) -> String {
+    match object_or_link {
+        ap_model::ObjectOrLink::Link(link) => link.href.to_string(),
+        ap_model::ObjectOrLink::Object(object) => object.id.clone().unwrap(),
+    }
+}
+
+#[test]
+fn to_activity_with_cw() {
+    let note = parse_note(
+        r#"{
+            "id": "9n1",
+            "text": "Hello\n\nWorld <3",
+            "createdAt": "2024-01-01T00:00:00.000Z",
+            "cw": "Greeting",
+            "visibility": "public"
+        }"#,
+    );
+    let activity = note.to_activity(&CONTEXT);
+
+    assert_eq!(activity.typ, vec!["Create".to_string()]);
+    assert_eq!(
+        activity.id.as_deref(),
+        Some("https://misskey.example/notes/9n1/activity")
+    );
+    let object = object_of(&activity);
+    assert_eq!(object.typ, vec!["Note".to_string()]);
+    assert_eq!(
+        object.object_items.content,
+        vec!["<p>Hello</p><p>World &lt;3</p>".to_string()]
+    );
+    assert_eq!(object.object_items.summary, vec!["Greeting".to_string()]);
+    assert_eq!(object.activity_streams_ext_items.sensitive, Some(true));
+    assert_eq!(
+        object
+            .object_items
+            .to
+            .iter()
+            .map(href_of)
+            .collect::<Vec<_>>(),
+        vec!["https://www.w3.org/ns/activitystreams#Public".to_string()]
+    );
+}
+
+#[test]
+fn is_pure_renote() {
+    let renote = parse_note(
+        r#"{
+            "id": "9n2",
+            "text": null,
+            "createdAt": "2024-01-01T00:00:00.000Z",
+            "cw": null,
+            "renoteId": "9r1",
+            "visibility": "home"
+        }"#,
+    );
+    assert!(renote.is_pure_renote());
+
+    let quote = parse_note(
+        r#"{
+            "id": "9n3",
+            "text": "Look",
+            "createdAt": "2024-01-01T00:00:00.000Z",
+            "cw": null,
+            "renoteId": "9r1",
+            "visibility": "public"
+        }"#,
+    );
+    assert!(!quote.is_pure_renote());
+}
+
+#[test]
+fn to_activity_quote() {
+    let note = parse_note(
+        r#"{
+            "id": "9n3",
+            "text": "Look",
+            "createdAt": "2024-01-01T00:00:00.000Z",
+            "cw": null,
+            "renoteId": "9r1",
+            "visibility": "public"
+        }"#,
+    );
+    let activity = note.to_activity(&CONTEXT);
+
+    assert_eq!(activity.typ, vec!["Create".to_string()]);
+    let object = object_of(&activity);
+    assert_eq!(
+        object.object_items.content,
+        vec![
+            "<p>Look</p><p><span class=\"quote-inline\">RE: <a href=\"https://misskey.example/notes/9r1\">https://misskey.example/notes/9r1</a></span></p>"
+                .to_string()
+        ]
+    );
+    match object.object_items.tag.as_slice() {
+        [ap_model::ObjectOrLink::Link(link)] => {
+            assert_eq!(link.href.to_string(), "https://misskey.example/notes/9r1");
+            assert_eq!(
+                link.rel,
+                vec!["https://misskey-hub.net/ns#_misskey_quote".to_string()]
+            );
+        }
+        _ => panic!("The quote should be tagged."),
+    }
+}
+
+#[test]
+fn to_activity_poll() {
+    let note = parse_note(
+        r#"{
+            "id": "9n4",
+            "text": "Which?",
+            "createdAt": "2024-01-01T00:00:00.000Z",
+            "cw": null,
+            "poll": {
+                "choices": ["A", "B"],
+                "multiple": true,
+                "expiresAt": "2024-01-02T00:00:00.000Z"
+            },
+            "visibility": "public"
+        }"#,
+    );
+    let activity = note.to_activity(&CONTEXT);
+
+    let object = object_of(&activity);
+    assert_eq!(object.typ, vec!["Question".to_string()]);
+    assert!(object.question_items.one_of.is_empty());
+    let choices: Vec<Vec<String>> = object
+        .question_items
+        .any_of
+        .iter()
+        .map(|choice| match choice {
+            ap_model::ObjectOrLink::Object(choice) => choice.object_items.name.clone(),
+            ap_model::ObjectOrLink::Link(_) => vec![],
+        })
+        .collect();
+    assert_eq!(choices, vec![vec!["A".to_string()], vec!["B".to_string()]]);
+    assert!(object.object_items.end_time.is_some());
+}
+
+#[test]
+fn to_activity_sensitive_files() {
+    let note = parse_note(
+        r#"{
+            "id": "9n5",
+            "text": null,
+            "createdAt": "2024-01-01T00:00:00.000Z",
+            "cw": null,
+            "files": [
+                {
+                    "id": "9f1",
+                    "name": "cat.png",
+                    "type": "image/png",
+                    "url": "https://misskey.example/files/cat.png",
+                    "comment": "A cat",
+                    "isSensitive": true
+                },
+                {
+                    "id": "9f2",
+                    "name": "lost.png",
+                    "type": "image/png",
+                    "url": null
+                }
+            ],
+            "visibility": "public"
+        }"#,
+    );
+    let activity = note.to_activity(&CONTEXT);
+
+    assert_eq!(activity.typ, vec!["Create".to_string()]);
+    let object = object_of(&activity);
+    assert_eq!(object.activity_streams_ext_items.sensitive, Some(true));
+    match object.object_items.attachment.as_slice() {
+        [ap_model::ObjectOrLink::Object(document)] => {
+            assert_eq!(
+                document
+                    .object_items
+                    .url
+                    .as_ref()
+                    .map(|x| x.href.to_string()),
+                Some("https://misskey.example/files/cat.png".to_string())
+            );
+            assert_eq!(
+                document.object_items.media_type,
+                vec!["image/png".to_string()]
+            );
+            assert_eq!(document.object_items.name, vec!["A cat".to_string()]);
+        }
+        _ => panic!("Only files with URLs should be attached."),
+    }
+}
+
+#[test]
+fn export_names() {
+    let note = parse_note(
+        r#"{
+            "id": "9n6",
+            "text": null,
+            "createdAt": "2024-01-01T00:00:00.000Z",
+            "cw": null,
+            "files": [{"id": "9f1", "name": "cat.png", "type": "image/png", "url": null}],
+            "visibility": "public"
+        }"#,
+    );
+    let file = &note.files[0];
+
+    assert_eq!(
+        file.export_names(true),
+        vec![
+            "9f1".to_string(),
+            "9f1.png".to_string(),
+            "cat.png".to_string()
+        ]
+    );
+    assert_eq!(
+        file.export_names(false),
+        vec!["9f1".to_string(), "9f1.png".to_string()]
+    );
+}
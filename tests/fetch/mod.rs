@@ -0,0 +1,2 @@
+pub mod misskey_export;
+pub mod object_path;
@@ -0,0 +1,107 @@
+use archivedon::fetch::object_path::{ObjectPathResolver, ObjectPathRuleKind};
+
+fn resolve(kind: ObjectPathRuleKind, id: &str) -> Option<String> {
+    ObjectPathResolver::from_kinds(&[kind]).resolve(id).ok()
+}
+
+#[test]
+fn resolve_each_kind() {
+    let cases = [
+        (
+            ObjectPathRuleKind::StatusesOrNotes,
+            "https://mastodon.example/users/alice/statuses/111222333",
+            Some("111222333"),
+        ),
+        (
+            ObjectPathRuleKind::StatusesOrNotes,
+            "https://misskey.example/notes/9abcdefghi",
+            Some("9abcdefghi"),
+        ),
+        (
+            ObjectPathRuleKind::StatusesOrNotes,
+            "https://mastodon.example/users/alice/statuses/111/activity",
+            None,
+        ),
+        (
+            ObjectPathRuleKind::Gotosocial,
+            "https://gts.example/users/alice/statuses/01HBZ4TZGQ5M6B2X6B7YQ3ZK8W",
+            Some("01HBZ4TZGQ5M6B2X6B7YQ3ZK8W"),
+        ),
+        (
+            ObjectPathRuleKind::Gotosocial,
+            "https://gts.example/users/alice/statuses/01hbz4tzgq5m6b2x6b7yq3zk8w",
+            None,
+        ),
+        (
+            ObjectPathRuleKind::Pleroma,
+            "https://pleroma.example/objects/0f1e2d3c-4b5a-6978-8a9b-0c1d2e3f4a5b",
+            Some("0f1e2d3c-4b5a-6978-8a9b-0c1d2e3f4a5b"),
+        ),
+        (
+            ObjectPathRuleKind::Pleroma,
+            "https://pleroma.example/objects/not-a-uuid",
+            None,
+        ),
+        (
+            ObjectPathRuleKind::Lemmy,
+            "https://lemmy.example/post/123",
+            Some("post-123"),
+        ),
+        (
+            ObjectPathRuleKind::Lemmy,
+            "https://lemmy.example/comment/456",
+            Some("comment-456"),
+        ),
+        (
+            ObjectPathRuleKind::Lemmy,
+            "https://lemmy.example/c/community",
+            None,
+        ),
+        (
+            ObjectPathRuleKind::Peertube,
+            "https://peertube.example/videos/watch/0f1e2d3c-4b5a-6978-8a9b-0c1d2e3f4a5b",
+            Some("0f1e2d3c-4b5a-6978-8a9b-0c1d2e3f4a5b"),
+        ),
+        (
+            ObjectPathRuleKind::Peertube,
+            "https://peertube.example/videos/watch/123",
+            None,
+        ),
+        (
+            ObjectPathRuleKind::Hash,
+            "https://example.com/any/id",
+            Some("a254c5f1e1ac2d7bf6b219da387372c1c4a091f9bfc0c8b005a39bfdc1d7e1fa"),
+        ),
+    ];
+
+    for (kind, id, expected) in cases {
+        assert_eq!(
+            resolve(kind, id).as_deref(),
+            expected,
+            "kind={kind:?}, id={id}"
+        );
+    }
+}
+
+#[test]
+fn resolve_in_order() {
+    let resolver = ObjectPathResolver::from_kinds(&ObjectPathRuleKind::all());
+
+    assert_eq!(
+        resolver
+            .resolve("https://lemmy.example/comment/456")
+            .unwrap(),
+        "comment-456"
+    );
+    // IDs matching with no other rules fall back to hashes.
+    assert_eq!(
+        resolver
+            .resolve("https://example.com/any/id")
+            .unwrap()
+            .len(),
+        64
+    );
+    assert!(ObjectPathResolver::from_kinds(&[])
+        .resolve("https://example.com/any/id")
+        .is_err());
+}
@@ -0,0 +1,34 @@
+use archivedon::host_meta::HostMeta;
+use serde_json::Value;
+
+#[test]
+fn serialize_xrd() {
+    let data = HostMeta::webfinger("https", "example.com");
+
+    let expected_data = r#"<?xml version="1.0" encoding="UTF-8"?>
+<XRD xmlns="http://docs.oasis-open.org/ns/xri/xrd-1.0">
+  <Link rel="lrdd" type="application/jrd+json" template="https://example.com/.well-known/webfinger?resource={uri}"/>
+</XRD>
+"#;
+
+    assert_eq!(data.to_xrd(), expected_data);
+}
+
+#[test]
+fn serialize_json() {
+    let data = HostMeta::webfinger("https", "example.com");
+    let serialized_data = serde_json::to_value(data).unwrap();
+
+    let expected_data = r#"{
+        "links": [
+            {
+                "rel": "lrdd",
+                "type": "application/jrd+json",
+                "template": "https://example.com/.well-known/webfinger?resource={uri}"
+            }
+        ]
+    }"#;
+    let expected_data: Value = serde_json::from_str(expected_data).unwrap();
+
+    assert_eq!(serialized_data, expected_data);
+}
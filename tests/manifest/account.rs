@@ -0,0 +1,36 @@
+use archivedon::manifest::{Manifest, ManifestAccount};
+use chrono::{TimeZone, Utc};
+
+fn sample_account(account: &str, posts_count: usize) -> ManifestAccount {
+    ManifestAccount {
+        account: account.to_string(),
+        actor_url: format!("https://archive.example/users/{account}/actor.json"),
+        posts_count,
+        archived_at: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+    }
+}
+
+#[test]
+fn upsert_and_find_accounts() {
+    let mut manifest = Manifest::new(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+    manifest.upsert_account(sample_account("bob@example.com", 2));
+    manifest.upsert_account(sample_account("alice@example.com", 3));
+    manifest.upsert_account(sample_account("carol@example.com", 5));
+    manifest.upsert_account(sample_account("bob@example.com", 7));
+
+    assert_eq!(
+        manifest
+            .accounts
+            .iter()
+            .map(|x| x.account.as_str())
+            .collect::<Vec<_>>(),
+        vec!["alice@example.com", "bob@example.com", "carol@example.com"]
+    );
+    assert_eq!(
+        manifest.account("bob@example.com"),
+        Some(&sample_account("bob@example.com", 7))
+    );
+    assert_eq!(manifest.account("dave@example.com"), None);
+    assert_eq!(manifest.users_count(), 3);
+    assert_eq!(manifest.posts_count(), 15);
+}
@@ -0,0 +1,16 @@
+use archivedon::nodeinfo::Version;
+
+#[test]
+fn parse() {
+    assert_eq!(Version::parse("2.0"), Some(Version::V2_0));
+    assert_eq!(Version::parse("1.1"), None);
+    assert_eq!(Version::parse("3.0"), None);
+}
+
+#[test]
+fn content_type() {
+    assert_eq!(
+        Version::V2_1.content_type(),
+        r#"application/json; profile="http://nodeinfo.diaspora.software/ns/schema/2.1#""#
+    );
+}
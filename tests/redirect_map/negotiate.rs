@@ -0,0 +1,41 @@
+use archivedon::redirect_map::RedirectMap;
+use url::Url;
+
+fn sample_map() -> RedirectMap {
+    let mut map = RedirectMap::new();
+    map.insert_entry(
+        "application/activity+json".to_string(),
+        &Url::parse("https://example.com/actor.json").unwrap(),
+    );
+    map.insert_entry(
+        "*/*".to_string(),
+        &Url::parse("https://example.com/profile.html").unwrap(),
+    );
+    map
+}
+
+#[test]
+fn negotiate_activity_json() {
+    let map = sample_map();
+
+    assert_eq!(
+        map.negotiate(Some(
+            r#"application/activity+json, application/ld+json; profile="https://www.w3.org/ns/activitystreams""#
+        )),
+        Some(Url::parse("https://example.com/actor.json").unwrap())
+    );
+}
+
+#[test]
+fn negotiate_fallback() {
+    let map = sample_map();
+
+    assert_eq!(
+        map.negotiate(Some("text/html,application/xhtml+xml;q=0.9,*/*;q=0.8")),
+        Some(Url::parse("https://example.com/profile.html").unwrap())
+    );
+    assert_eq!(
+        map.negotiate(None),
+        Some(Url::parse("https://example.com/profile.html").unwrap())
+    );
+}
@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+
+use archivedon::resource_path::ResourcePath;
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap().to_str().unwrap().to_string()
+}
+
+fn sample_resource_path() -> ResourcePath {
+    ResourcePath::new(PathBuf::from("/archive"))
+}
+
+#[test]
+fn webfinger_alias_path_short() {
+    let resource_path = sample_resource_path();
+
+    assert_eq!(
+        resource_path.webfinger_alias_path("acct:alice@example.com"),
+        PathBuf::from("/archive/webfinger_alias/_acct:alice@example.com.json")
+    );
+    assert_eq!(
+        resource_path.webfinger_alias_path("https://example.com/@alice"),
+        PathBuf::from("/archive/webfinger_alias/_..aHR0cHM6Ly9leGFtcGxlLmNvbS9AYWxpY2U.json")
+    );
+}
+
+#[test]
+fn webfinger_alias_path_long() {
+    let resource_path = sample_resource_path();
+    let long_url = format!("https://example.com/users/{}", "a".repeat(300));
+    let other_long_url = format!("https://example.com/users/{}", "b".repeat(300));
+
+    let path = resource_path.webfinger_alias_path(&long_url);
+    assert_eq!(
+        path.parent(),
+        Some(resource_path.webfinger_alias_root_dir.as_path())
+    );
+    assert!(file_name(&path).starts_with("_..."));
+    assert!(file_name(&path).len() <= 255);
+    assert_eq!(path, resource_path.webfinger_alias_path(&long_url));
+    assert_ne!(path, resource_path.webfinger_alias_path(&other_long_url));
+}
+
+#[test]
+fn component_length_boundary() {
+    let resource_path = sample_resource_path();
+
+    // `_` and 249 characters are kept as they are.
+    let kept = "a".repeat(249);
+    assert_eq!(
+        file_name(&resource_path.webfinger_path(&kept)),
+        format!("_{kept}.json")
+    );
+
+    let hashed = "a".repeat(250);
+    let name = file_name(&resource_path.webfinger_path(&hashed));
+    assert!(name.starts_with("_..."));
+    assert_eq!(name.len(), "_...".len() + 64 + ".json".len());
+}
+
+#[test]
+fn redirect_map_path_long() {
+    let resource_path = sample_resource_path();
+    let long_component = "c".repeat(300);
+
+    let path = resource_path.redirect_map_path(
+        "example.com",
+        &format!("/users/{long_component}/statuses/{long_component}"),
+    );
+    let components = path
+        .strip_prefix(&resource_path.redirect_map_root_dir)
+        .unwrap()
+        .iter()
+        .map(|x| x.to_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(components.len(), 5);
+    assert_eq!(components[0], "_example.com");
+    assert_eq!(components[1], "_users");
+    assert!(components[2].starts_with("_..."));
+    assert_eq!(components[3], "_statuses");
+    assert!(components[4].starts_with("_...") && components[4].ends_with(".json"));
+    assert!(components.iter().all(|x| x.len() <= 255));
+}
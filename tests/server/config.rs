@@ -0,0 +1,362 @@
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use archivedon::server::cmd::serve::Options;
+use archivedon::server::config::{Config, HostConfig, SiteConfig};
+use warp::http::StatusCode;
+
+fn minimal_config() -> Config {
+    Config {
+        port: Some(8080),
+        resource_dir: Some(PathBuf::from("./out")),
+        expose_url_base: Some("https://archive.example/".to_string()),
+        ..Default::default()
+    }
+}
+
+fn into_error(config: Config) -> String {
+    match config.into_options() {
+        Ok(_) => panic!("The config should be invalid."),
+        Err(err) => err.to_string(),
+    }
+}
+
+fn load_config(file_name: &str, content: &str) -> Result<Config, String> {
+    let dir = std::env::temp_dir().join(format!("archivedon-test-config-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(file_name);
+    fs::write(&path, content).unwrap();
+    let result = Config::load(&path).map_err(|err| err.to_string());
+    fs::remove_file(&path).unwrap();
+    result
+}
+
+fn sample_site() -> SiteConfig {
+    SiteConfig {
+        hosts: vec!["old.example".to_string()],
+        resource_dir: PathBuf::from("./site"),
+        expose_url_base: "https://site.example/".to_string(),
+        gone_top_link: None,
+        node: Default::default(),
+    }
+}
+
+#[test]
+fn defaults() {
+    let options: Options = minimal_config().into_options().unwrap();
+
+    assert_eq!(options.addrs, vec![IpAddr::from([0, 0, 0, 0])]);
+    assert_eq!(options.port, 8080);
+    let default_site = options.default_site.unwrap();
+    assert_eq!(default_site.resource_dir, PathBuf::from("./out"));
+    assert_eq!(
+        default_site.expose_url_base.as_str(),
+        "https://archive.example/"
+    );
+    assert!(!default_site.gone_top_link);
+    assert!(options.sites.is_empty());
+    assert_eq!(options.index_max_entries, None);
+    assert_eq!(options.reload_interval, None);
+    assert_eq!(options.drain_timeout, Duration::from_secs(30));
+    assert!(!options.metrics);
+    assert_eq!(options.admin_addr, None);
+    assert_eq!(options.cors.allow_origins, vec!["*".to_string()]);
+    assert!(options.hosts.is_empty());
+}
+
+#[test]
+fn required_fields() {
+    let cases = [
+        (
+            Config {
+                port: None,
+                ..minimal_config()
+            },
+            "port is required",
+        ),
+        (
+            Config {
+                resource_dir: None,
+                ..minimal_config()
+            },
+            "resource_dir is required",
+        ),
+        (
+            Config {
+                expose_url_base: None,
+                ..minimal_config()
+            },
+            "expose_url_base is required",
+        ),
+        (
+            Config {
+                resource_dir: None,
+                expose_url_base: None,
+                ..minimal_config()
+            },
+            "resource_dir is required",
+        ),
+    ];
+    for (config, expected) in cases {
+        let err = into_error(config);
+        assert!(err.contains(expected), "err={err}");
+    }
+}
+
+#[test]
+fn sites_without_default_site() {
+    let options = Config {
+        resource_dir: None,
+        expose_url_base: None,
+        node: archivedon::server::config::NodeConfig {
+            name: Some("Archive".to_string()),
+            ..Default::default()
+        },
+        sites: vec![sample_site()],
+        ..minimal_config()
+    }
+    .into_options()
+    .unwrap();
+
+    assert!(options.default_site.is_none());
+    assert_eq!(options.sites.len(), 1);
+    assert_eq!(options.sites[0].hosts, vec!["old.example".to_string()]);
+    // NodeInfo metadata defaults to the top level one.
+    assert_eq!(
+        options.sites[0].node_metadata.node_name.as_deref(),
+        Some("Archive")
+    );
+}
+
+#[test]
+fn addresses() {
+    let options = Config {
+        addrs: vec!["127.0.0.1".to_string(), "::1".to_string()],
+        admin_addr: Some("127.0.0.1:9090".to_string()),
+        ..minimal_config()
+    }
+    .into_options()
+    .unwrap();
+    assert_eq!(
+        options.addrs,
+        vec![
+            IpAddr::from([127, 0, 0, 1]),
+            IpAddr::from([0, 0, 0, 0, 0, 0, 0, 1])
+        ]
+    );
+    assert_eq!(
+        options.admin_addr,
+        Some(SocketAddr::from(([127, 0, 0, 1], 9090)))
+    );
+
+    let err = into_error(Config {
+        addrs: vec!["localhost".to_string()],
+        ..minimal_config()
+    });
+    assert!(err.contains("Invalid addrs"), "err={err}");
+
+    let err = into_error(Config {
+        admin_addr: Some("127.0.0.1".to_string()),
+        ..minimal_config()
+    });
+    assert!(err.contains("Invalid admin_addr"), "err={err}");
+}
+
+#[test]
+fn expose_url_base() {
+    for value in ["archive.example", "ftp://archive.example/", "http://"] {
+        let err = into_error(Config {
+            expose_url_base: Some(value.to_string()),
+            ..minimal_config()
+        });
+        assert!(err.contains("expose_url_base"), "value={value}, err={err}");
+    }
+
+    let err = into_error(Config {
+        sites: vec![SiteConfig {
+            expose_url_base: "site.example".to_string(),
+            ..sample_site()
+        }],
+        ..minimal_config()
+    });
+    assert!(err.contains("expose_url_base"), "err={err}");
+}
+
+#[test]
+fn redirect_status() {
+    for status in [301, 302, 303, 307, 308] {
+        let mut config = minimal_config();
+        config.hosts.insert(
+            "Old.Example".to_string(),
+            HostConfig {
+                gone_top_link: None,
+                redirect_status: Some(status),
+            },
+        );
+        let options = config.into_options().unwrap();
+        assert_eq!(
+            options.hosts["old.example"].redirect_status,
+            StatusCode::from_u16(status).unwrap()
+        );
+    }
+
+    let mut config = minimal_config();
+    config
+        .hosts
+        .insert("old.example".to_string(), HostConfig::default());
+    let options = config.into_options().unwrap();
+    assert_eq!(
+        options.hosts["old.example"].redirect_status,
+        StatusCode::MOVED_PERMANENTLY
+    );
+
+    for status in [200, 304, 404] {
+        let mut config = minimal_config();
+        config.hosts.insert(
+            "old.example".to_string(),
+            HostConfig {
+                gone_top_link: None,
+                redirect_status: Some(status),
+            },
+        );
+        let err = into_error(config);
+        assert!(err.contains("Invalid redirect_status"), "err={err}");
+    }
+}
+
+#[test]
+fn cors_origins() {
+    let allow_origins = vec![
+        "https://a.example".to_string(),
+        "http://b.example:8080".to_string(),
+    ];
+    let mut config = minimal_config();
+    config.cors.allow_origins = Some(allow_origins.clone());
+    assert_eq!(
+        config.into_options().unwrap().cors.allow_origins,
+        allow_origins
+    );
+
+    for origin in [
+        "https://a.example/",
+        "https://a.example/path",
+        "a.example",
+        "https://A.example",
+    ] {
+        let mut config = minimal_config();
+        config.cors.allow_origins = Some(vec![origin.to_string()]);
+        let err = into_error(config);
+        assert!(
+            err.contains("Invalid cors.allow_origins"),
+            "origin={origin}, err={err}"
+        );
+    }
+}
+
+#[test]
+fn positive_numbers() {
+    let err = into_error(Config {
+        reload_interval_secs: Some(0),
+        ..minimal_config()
+    });
+    assert!(err.contains("reload_interval_secs"), "err={err}");
+
+    let err = into_error(Config {
+        index: Some(true),
+        index_max_entries: Some(0),
+        ..minimal_config()
+    });
+    assert!(err.contains("index_max_entries"), "err={err}");
+
+    let options = Config {
+        index: Some(true),
+        ..minimal_config()
+    }
+    .into_options()
+    .unwrap();
+    assert_eq!(options.index_max_entries, Some(100_000));
+
+    for index in [None, Some(false)] {
+        let err = into_error(Config {
+            index,
+            index_max_entries: Some(10),
+            ..minimal_config()
+        });
+        assert!(err.contains("requires index"), "err={err}");
+    }
+}
+
+#[test]
+fn toml_and_yaml() {
+    let toml = r#"
+addrs = ["127.0.0.1"]
+port = 8080
+resource_dir = "./out"
+expose_url_base = "https://archive.example/"
+index = true
+
+[cors]
+allow_origins = ["https://a.example"]
+
+[cache]
+static_max_age_secs = 60
+
+[hosts."old.example"]
+redirect_status = 308
+"#;
+    let yaml = r#"
+addrs: ["127.0.0.1"]
+port: 8080
+resource_dir: ./out
+expose_url_base: https://archive.example/
+index: true
+cors:
+  allow_origins: ["https://a.example"]
+cache:
+  static_max_age_secs: 60
+hosts:
+  old.example:
+    redirect_status: 308
+"#;
+
+    for (file_name, content) in [
+        ("config.toml", toml),
+        ("config.yaml", yaml),
+        ("config.yml", yaml),
+    ] {
+        let options = load_config(file_name, content)
+            .unwrap()
+            .into_options()
+            .unwrap();
+        assert_eq!(options.addrs, vec![IpAddr::from([127, 0, 0, 1])]);
+        assert_eq!(options.port, 8080);
+        assert_eq!(options.index_max_entries, Some(100_000));
+        assert_eq!(
+            options.cors.allow_origins,
+            vec!["https://a.example".to_string()]
+        );
+        assert_eq!(
+            options.cache.static_json_max_age,
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            options.hosts["old.example"].redirect_status,
+            StatusCode::PERMANENT_REDIRECT
+        );
+    }
+}
+
+#[test]
+fn invalid_files() {
+    let err = load_config("config.json", "{}").err().unwrap();
+    assert!(err.contains("Unknown config file format"), "err={err}");
+
+    let err = load_config("config.toml", "port = \"8080\"").err().unwrap();
+    assert!(err.contains("Invalid config file"), "err={err}");
+
+    // Unknown fields are rejected to find typos.
+    let err = load_config("config.yaml", "prot: 8080").err().unwrap();
+    assert!(err.contains("Invalid config file"), "err={err}");
+}
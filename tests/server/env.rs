@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use archivedon::server::env::{CacheOptions, Env, HostOptions};
+use warp::http::StatusCode;
+
+use super::site_options;
+
+#[test]
+fn hosts() {
+    let mut hosts = HashMap::new();
+    hosts.insert(
+        "old.example".to_string(),
+        HostOptions {
+            gone_top_link: None,
+            redirect_status: StatusCode::PERMANENT_REDIRECT,
+        },
+    );
+    let env = Env::load(
+        Some(site_options("https://archive.example/", vec![])),
+        vec![site_options(
+            "https://other.example:8443/",
+            vec!["legacy.example".to_string()],
+        )],
+        hosts,
+        CacheOptions::default(),
+        None,
+    )
+    .unwrap();
+
+    for host in [
+        "archive.example",
+        "Archive.Example:443",
+        "other.example:8443",
+    ] {
+        assert!(env.is_expose_host(host), "host={host}");
+    }
+    for host in ["legacy.example", "old.example", "127.0.0.1:8080"] {
+        assert!(!env.is_expose_host(host), "host={host}");
+    }
+
+    assert_eq!(
+        env.configured_host("Legacy.Example:80"),
+        Some("legacy.example".to_string())
+    );
+    assert_eq!(
+        env.configured_host("old.example"),
+        Some("old.example".to_string())
+    );
+    assert_eq!(env.configured_host("unknown.example"), None);
+
+    assert_eq!(
+        env.redirect_status("old.example:443"),
+        StatusCode::PERMANENT_REDIRECT
+    );
+    assert_eq!(
+        env.redirect_status("legacy.example"),
+        StatusCode::MOVED_PERMANENTLY
+    );
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use archivedon::server::env::site::SiteOptions;
+use archivedon::server::env::{CacheOptions, Env, HostOptions};
+use archivedon::server::handler::gone;
+use serde_json::Value;
+use warp::http::StatusCode;
+use warp::Reply;
+
+use super::site_options;
+
+fn sample_env(default_site: Option<SiteOptions>, sites: Vec<SiteOptions>) -> Arc<Env> {
+    let mut hosts = HashMap::new();
+    hosts.insert(
+        "old.example".to_string(),
+        HostOptions {
+            gone_top_link: Some(false),
+            redirect_status: StatusCode::MOVED_PERMANENTLY,
+        },
+    );
+    Env::load(default_site, sites, hosts, CacheOptions::default(), None).unwrap()
+}
+
+async fn request(
+    env: Arc<Env>,
+    host: Option<&str>,
+    path: &str,
+    accept: Option<&str>,
+) -> (StatusCode, Option<String>, Vec<u8>) {
+    let full_path = warp::test::request()
+        .path(path)
+        .filter(&warp::path::full())
+        .await
+        .unwrap();
+    let reply = gone::handle(
+        env,
+        host.map(|x| x.to_string()),
+        full_path,
+        accept.map(|x| x.to_string()),
+    )
+    .await
+    .unwrap();
+    let response = reply.into_response();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .map(|x| x.to_str().unwrap().to_string());
+    let body = warp::hyper::body::to_bytes(response.into_body())
+        .await
+        .unwrap();
+    (status, content_type, body.to_vec())
+}
+
+async fn tombstone_id(env: Arc<Env>, host: Option<&str>, path: &str) -> String {
+    let (status, content_type, body) =
+        request(env, host, path, Some("application/activity+json")).await;
+    assert_eq!(status, StatusCode::GONE);
+    assert_eq!(content_type.as_deref(), Some("application/activity+json"));
+    let tombstone: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(tombstone["type"], "Tombstone");
+    tombstone["id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn tombstone_id_by_configured_host() {
+    let env = sample_env(
+        Some(site_options("https://archive.example/", vec![])),
+        vec![site_options(
+            "http://other.example:8080/",
+            vec!["legacy.example".to_string()],
+        )],
+    );
+
+    let cases = [
+        (
+            Some("archive.example"),
+            "https://archive.example/users/alice/statuses/1",
+        ),
+        (
+            Some("old.example:443"),
+            "https://old.example/users/alice/statuses/1",
+        ),
+        (
+            Some("other.example:8080"),
+            "http://other.example:8080/users/alice/statuses/1",
+        ),
+        (
+            Some("Legacy.Example"),
+            "http://legacy.example/users/alice/statuses/1",
+        ),
+        (
+            Some("evil.example"),
+            "https://archive.example/users/alice/statuses/1",
+        ),
+        (
+            Some("evil.example/path?"),
+            "https://archive.example/users/alice/statuses/1",
+        ),
+        (None, "https://archive.example/users/alice/statuses/1"),
+    ];
+    for (host, expected) in cases {
+        assert_eq!(
+            tombstone_id(env.clone(), host, "/users/alice/statuses/1").await,
+            expected,
+            "host={host:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn html_for_browsers() {
+    let env = sample_env(
+        Some(site_options("https://archive.example/", vec![])),
+        vec![],
+    );
+
+    let (status, content_type, body) = request(
+        env.clone(),
+        Some("archive.example"),
+        "/notes/1",
+        Some("text/html"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::GONE);
+    assert!(content_type.unwrap().starts_with("text/html"));
+    assert!(String::from_utf8(body)
+        .unwrap()
+        .contains("<a href=\"https://archive.example/\">"));
+
+    // The top link is disabled for the host.
+    let (_, _, body) = request(env, Some("old.example"), "/notes/1", Some("text/html")).await;
+    assert!(!String::from_utf8(body).unwrap().contains("<a href="));
+}
+
+#[tokio::test]
+async fn plain_text_for_others() {
+    let env = sample_env(
+        Some(site_options("https://archive.example/", vec![])),
+        vec![],
+    );
+
+    for accept in [None, Some("*/*"), Some("application/json")] {
+        let (status, content_type, _) =
+            request(env.clone(), Some("archive.example"), "/notes/1", accept).await;
+        assert_eq!(status, StatusCode::GONE, "accept={accept:?}");
+        assert!(
+            content_type.is_none_or(|x| x != "application/activity+json"),
+            "accept={accept:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn not_found_without_site() {
+    let env = sample_env(None, vec![site_options("https://archive.example/", vec![])]);
+
+    let (status, _, _) = request(
+        env,
+        Some("unknown.example"),
+        "/notes/1",
+        Some("application/activity+json"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,133 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use archivedon::redirect_map::RedirectMap;
+use archivedon::resource_path::ResourcePath;
+use archivedon::server::env::index::{Indexed, ResourceIndex};
+use serde_json::json;
+use url::Url;
+
+fn write_json(path: &Path, value: &serde_json::Value) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, serde_json::to_vec(value).unwrap()).unwrap();
+}
+
+/**
+ * An archive with 2 redirect maps, 1 WebFinger resource and 1 alias.
+ */
+fn sample_archive(name: &str) -> (PathBuf, ResourcePath) {
+    let root_dir = std::env::temp_dir().join(format!(
+        "archivedon-test-index-{name}-{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&root_dir);
+    let resource_path = ResourcePath::new(root_dir.clone());
+
+    fs::create_dir_all(&root_dir).unwrap();
+    fs::write(&resource_path.index_html_path, "<!DOCTYPE html>").unwrap();
+    write_json(
+        &resource_path.manifest_path,
+        &json!({
+            "updated_at": "2024-01-01T00:00:00Z",
+            "accounts": [],
+        }),
+    );
+
+    for url_path in ["/users/alice", "/users/alice/statuses/1"] {
+        let mut map = RedirectMap::new();
+        map.insert_entry(
+            "*/*".to_string(),
+            &Url::parse(&format!("https://archive.example{url_path}")).unwrap(),
+        );
+        write_json(
+            &resource_path.redirect_map_path("old.example", url_path),
+            &serde_json::to_value(&map).unwrap(),
+        );
+    }
+    write_json(
+        &resource_path.webfinger_path("acct:alice@old.example"),
+        &json!({ "subject": "acct:alice@old.example" }),
+    );
+    write_json(
+        &resource_path.webfinger_alias_path("https://old.example/users/alice"),
+        &json!({ "subject": "acct:alice@old.example" }),
+    );
+    // Files other than JSON are never indexed.
+    fs::write(
+        resource_path.webfinger_root_dir.join("README.txt"),
+        "not indexed",
+    )
+    .unwrap();
+
+    (root_dir, resource_path)
+}
+
+#[test]
+fn load_without_index() {
+    let (root_dir, resource_path) = sample_archive("none");
+
+    let index = ResourceIndex::load(&resource_path, None);
+    assert!(index.archived_at.is_some());
+    assert_eq!(index.manifest.as_ref().map(|x| x.users_count()), Some(0));
+    assert!(index.manifest_modified.is_some());
+    assert!(index.files.is_none());
+    assert!(matches!(
+        index.indexed(
+            |files| &files.redirect_maps,
+            &resource_path.redirect_map_path("old.example", "/users/alice"),
+        ),
+        Indexed::NotIndexed
+    ));
+
+    fs::remove_dir_all(root_dir).unwrap();
+}
+
+#[test]
+fn load_all_files() {
+    let (root_dir, resource_path) = sample_archive("all");
+
+    let index = ResourceIndex::load(&resource_path, Some(100));
+    let files = index.files.as_ref().unwrap();
+    assert!(files.complete);
+    assert_eq!(files.redirect_maps.len(), 2);
+    assert_eq!(files.webfinger_resources.len(), 1);
+    assert_eq!(files.webfinger_aliases.len(), 1);
+
+    let found = index.indexed(
+        |files| &files.webfinger_aliases,
+        &resource_path.webfinger_alias_path("https://old.example/users/alice"),
+    );
+    assert!(matches!(found, Indexed::Found(alias) if alias.subject == "acct:alice@old.example"));
+    assert!(matches!(
+        index.indexed(
+            |files| &files.redirect_maps,
+            &resource_path.redirect_map_path("old.example", "/users/bob"),
+        ),
+        Indexed::Missing
+    ));
+
+    fs::remove_dir_all(root_dir).unwrap();
+}
+
+#[test]
+fn load_up_to_max_entries() {
+    let (root_dir, resource_path) = sample_archive("max");
+
+    let index = ResourceIndex::load(&resource_path, Some(3));
+    let files = index.files.as_ref().unwrap();
+    assert!(!files.complete);
+    assert_eq!(
+        files.redirect_maps.len() + files.webfinger_resources.len() + files.webfinger_aliases.len(),
+        3
+    );
+    // Files missing in the index may exist on the disk.
+    assert!(matches!(
+        index.indexed(
+            |files| &files.webfinger_aliases,
+            &resource_path.webfinger_alias_path("https://old.example/users/alice"),
+        ),
+        Indexed::NotIndexed
+    ));
+
+    fs::remove_dir_all(root_dir).unwrap();
+}
@@ -0,0 +1,23 @@
+use archivedon::server::env::site::SiteOptions;
+use archivedon::server::env::NodeMetadata;
+use url::Url;
+
+pub mod config;
+pub mod env;
+pub mod gone;
+pub mod index;
+
+fn site_options(expose_url_base: &str, hosts: Vec<String>) -> SiteOptions {
+    SiteOptions {
+        hosts,
+        resource_dir: std::env::temp_dir().join("archivedon-test-server"),
+        expose_url_base: Url::parse(expose_url_base).unwrap(),
+        gone_top_link: true,
+        node_metadata: NodeMetadata {
+            node_name: None,
+            node_description: None,
+            maintainer_name: None,
+            maintainer_email: None,
+        },
+    }
+}
@@ -0,0 +1,36 @@
+use archivedon::webfinger::acct::{normalize_resource, Acct};
+
+#[test]
+fn parse_variants() {
+    let expected = Acct {
+        user: "alice".to_string(),
+        host: "old.example".to_string(),
+    };
+
+    assert_eq!(Acct::parse("acct:alice@old.example").unwrap(), expected);
+    assert_eq!(Acct::parse("ACCT:Alice@Old.Example").unwrap(), expected);
+    assert_eq!(Acct::parse("alice@old.example").unwrap(), expected);
+    assert_eq!(Acct::parse("@alice@old.example").unwrap(), expected);
+    assert_eq!(Acct::parse("acct:%61lice@old.example").unwrap(), expected);
+    assert!(Acct::parse("acct:alice").is_err());
+}
+
+#[test]
+fn normalize_idn_host() {
+    let acct = Acct::parse("acct:alice@例え.テスト").unwrap();
+
+    assert_eq!(acct.host, "xn--r8jz45g.xn--zckzah");
+    assert_eq!(acct.to_string(), "acct:alice@xn--r8jz45g.xn--zckzah");
+}
+
+#[test]
+fn normalize_resources() {
+    assert_eq!(
+        normalize_resource("Alice@Old.Example"),
+        "acct:alice@old.example"
+    );
+    assert_eq!(
+        normalize_resource("https://Old.Example/users/alice"),
+        "https://old.example/users/alice"
+    );
+}
@@ -1 +1,2 @@
+pub mod acct;
 pub mod resource;